@@ -485,7 +485,7 @@ def filter_tsv(tsv_file:str, graph_a:MultiDiGraph, graph_b:MultiDiGraph) -> dict
         for line in reader:
             if line.startswith('#'):
                 continue
-            path_name,position,operation,node_a,node_b,breakpoint_a,breakpoint_b = line.split('\t')
+            path_name,position,operation,node_a,node_b,*_ = line.rstrip('\n').split('\t')
             if node_a in nodes_a and node_b in nodes_b:
                 v_1,v_2 = arrows_comparison.get((node_a,node_b),(0,0))
                 if operation == 'M':
@@ -1,20 +1,16 @@
+use crate::index_gfa_file::GfaIndex;
+use crate::segment_paths::PathSegmentation;
 use std::cmp::min;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::io::{self, BufRead, BufReader};
 
 pub fn annotate_editions(
     file_path1: &str,
     file_path2: &str,
     edition_results_file: &str,
-    node_sizes1: HashMap<String, u64>,
-    node_sizes2: HashMap<String, u64>,
-    path_positions1: HashMap<String, u64>,
-    path_positions2: HashMap<String, u64>,
-    path_lengths1: HashMap<String, u64>,
-    path_lengths2: HashMap<String, u64>,
-    path_types1: HashMap<String, char>,
-    path_types2: HashMap<String, char>,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
     intersection: Vec<String>,
 ) -> io::Result<()> {
     /*
@@ -23,6 +19,8 @@ pub fn annotate_editions(
     global_length: length of operation without taking other edits into account
     local_length: length of operation taking other edits into account
      */
+    let node_sizes1 = &index1.seq_lengths;
+    let node_sizes2 = &index2.seq_lengths;
 
     // Create empty vector hashmap to store edit positions
     let mut edit_positions: HashMap<String, Vec<u64>> = HashMap::new();
@@ -34,51 +32,12 @@ pub fn annotate_editions(
     // Sort the vector and seek the index of the current edit position
     // Look left and right, find the closest position, the difference is the length of the edit
     for path_name in edit_positions.keys().cloned().collect::<Vec<String>>() {
-        let pos1: u64 = path_positions1[&path_name];
-        let mut file1: BufReader<File> = BufReader::new(File::open(file_path1)?);
-        file1.seek(SeekFrom::Start(pos1))?;
-        let mut buffer1: [u8; 1] = [0; 1];
-        let mut cum_length = 0;
-        let max_length1 = path_lengths1[&path_name];
-        if let Some(positions) = edit_positions.get_mut(&path_name) {
-            positions.push(0);
-        }
-
-        while cum_length < max_length1 {
-            // We read the next node
-            let node_a = if path_types1[&path_name] == 'W' {
-                read_next_w_node(&mut file1, &mut buffer1)
-            } else {
-                read_next_p_node(&mut file1, &mut buffer1)
-            };
-            cum_length += node_sizes1[&node_a] as u64;
-            if let Some(positions) = edit_positions.get_mut(&path_name) {
-                positions.push(cum_length);
-            }
-        }
-    }
-
-    for path_name in edit_positions.keys().cloned().collect::<Vec<String>>() {
-        let pos2: u64 = path_positions2[&path_name];
-        let mut file2: BufReader<File> = BufReader::new(File::open(file_path2)?);
-        file2.seek(SeekFrom::Start(pos2))?;
-        let mut buffer2: [u8; 1] = [0; 1];
-        let mut cum_length = 0;
-        let max_length2 = path_lengths2[&path_name];
-        if let Some(positions) = edit_positions.get_mut(&path_name) {
-            positions.push(0);
-        }
-
-        while cum_length < max_length2 {
-            // We read the next node
-            let node_b = if path_types2[&path_name] == 'W' {
-                read_next_w_node(&mut file2, &mut buffer2)
-            } else {
-                read_next_p_node(&mut file2, &mut buffer2)
-            };
-            cum_length += node_sizes2[&node_b] as u64;
+        for (file_path, index) in [(file_path1, index1), (file_path2, index2)] {
+            let segmentation: PathSegmentation =
+                PathSegmentation::read(file_path, index, &path_name)?;
             if let Some(positions) = edit_positions.get_mut(&path_name) {
-                positions.push(cum_length);
+                positions.push(0);
+                positions.extend(segmentation.breakpoints());
             }
         }
     }
@@ -127,38 +86,3 @@ pub fn annotate_editions(
     }
     Ok(())
 }
-
-fn read_next_p_node(file: &mut BufReader<File>, buffer: &mut [u8; 1]) -> String {
-    /*
-     * Read the next node in the file, until a comma is found
-     * file: the file to read
-     * buffer: a buffer to read the file
-     */
-    let mut node = String::new();
-    while file.read(buffer).unwrap() > 0 {
-        if buffer[0] == b'+' || buffer[0] == b'-' || buffer[0] == b'\t' {
-            break;
-        }
-        if buffer[0] != b',' {
-            node.push(buffer[0] as char);
-        }
-    }
-    node
-}
-
-fn read_next_w_node(file: &mut BufReader<File>, buffer: &mut [u8; 1]) -> String {
-    /*
-     * Read the next node in the file, until a > or < is found
-     * file: the file to read
-     * buffer: a buffer to read the file
-     */
-    let mut node = String::new();
-    while file.read(buffer).unwrap() > 0 {
-        if buffer[0] == b'>' || buffer[0] == b'<' || buffer[0] == b'\t' {
-            break;
-        } else {
-            node.push(buffer[0] as char);
-        }
-    }
-    node
-}
@@ -0,0 +1,197 @@
+use crate::compute_distance::path_intersection;
+use crate::index_gfa_file::GfaIndex;
+use crate::segment_paths::PathSegmentation;
+use std::io::{self, Write};
+// The common coarsening of two graphs is the dual of their common refinement: each shared path is only cut
+// at the breakpoints found in both graphs (the equivalences), so that each of its intervals is a union of nodes in both graphs.
+// It is the skeleton both graphs agree on, written as BED: one record per shared breakpoint, covering the base after it.
+// As for the other metrics, path ends are not counted as breakpoints.
+// The fraction of the breakpoints of each graph that are shared is written apart, so that the BED file only holds records.
+
+pub fn coarsening(
+    segmentation_a: &PathSegmentation,
+    segmentation_b: &PathSegmentation,
+) -> Vec<u64> {
+    /*
+    Finds the breakpoints of a path found in both graphs
+
+    Arguments:
+    - segmentation_a: the segmentation of the path in the first graph
+    - segmentation_b: the segmentation of the path in the second graph, of the same length
+
+    Returns:
+    - the positions of the shared breakpoints, in increasing order
+    */
+    // Nodes end on breakpoints, the last one is the end of the path
+    let mut breakpoints_a: Vec<u64> = segmentation_a.breakpoints();
+    let mut breakpoints_b: Vec<u64> = segmentation_b.breakpoints();
+    breakpoints_a.pop();
+    breakpoints_b.pop();
+    let mut shared: Vec<u64> = Vec::new();
+    let (mut i, mut j): (usize, usize) = (0, 0);
+    while i < breakpoints_a.len() && j < breakpoints_b.len() {
+        if breakpoints_a[i] < breakpoints_b[j] {
+            i += 1;
+        } else if breakpoints_a[i] > breakpoints_b[j] {
+            j += 1;
+        } else {
+            // Both graphs have a breakpoint here
+            shared.push(breakpoints_a[i]);
+            i += 1;
+            j += 1;
+        }
+    }
+    shared
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoarseningReport {
+    // Name of each path, with its numbers of breakpoints in the first graph, in the second graph and in both graphs
+    pub counts: Vec<(String, u64, u64, u64)>,
+    // Paths that could not be compared: name, length in the first graph, length in the second graph
+    pub skipped_paths: Vec<(String, u64, u64)>,
+}
+
+pub fn shared_breakpoints(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
+) -> io::Result<CoarseningReport> {
+    /*
+    Computes the common coarsening of the paths found in both graphs
+
+    Arguments:
+    - file_path1: the path to the first GFA file
+    - file_path2: the path to the second GFA file
+    - index1: the index of the first GFA file
+    - index2: the index of the second GFA file
+
+    Output:
+    - Writes to standard output the shared breakpoints as BED, one record per breakpoint
+
+    Returns:
+    - the counts of breakpoints of each path, and the paths left out as they have different lengths in the two graphs
+    */
+    let mut writer: io::BufWriter<io::Stdout> = io::BufWriter::new(io::stdout());
+    let mut report: CoarseningReport = CoarseningReport::default();
+    for path_name in path_intersection(index1, index2) {
+        let length1: u64 = index1.path_lengths[&path_name];
+        let length2: u64 = index2.path_lengths[&path_name];
+        if length1 != length2 {
+            report.skipped_paths.push((path_name, length1, length2));
+            continue;
+        }
+        let segmentation_a: PathSegmentation =
+            PathSegmentation::read(file_path1, index1, &path_name)?;
+        let segmentation_b: PathSegmentation =
+            PathSegmentation::read(file_path2, index2, &path_name)?;
+        let shared: Vec<u64> = coarsening(&segmentation_a, &segmentation_b);
+        for position in shared.iter() {
+            writeln!(writer, "{}\t{}\t{}", path_name, position, position + 1)?;
+        }
+        // Breakpoints inside the path, path end excluded
+        report.counts.push((
+            path_name,
+            (segmentation_a.nodes.len() as u64).saturating_sub(1),
+            (segmentation_b.nodes.len() as u64).saturating_sub(1),
+            shared.len() as u64,
+        ));
+    }
+    writer.flush()?;
+    Ok(report)
+}
+
+pub fn write_summary<W: Write>(
+    counts: &[(String, u64, u64, u64)],
+    writer: &mut W,
+) -> io::Result<()> {
+    /*
+    Writes the fraction of breakpoints of each graph that are shared
+
+    Arguments:
+    - counts: the name of each path, with its numbers of breakpoints in the first graph, in the second graph and in both graphs
+    - writer: where the summary is written, as TSV, for each path and over all paths (as a path named '*')
+    */
+    let total: (String, u64, u64, u64) = counts.iter().fold(
+        ("*".to_string(), 0, 0, 0),
+        |(name, a, b, shared), (_, path_a, path_b, path_shared)| {
+            (name, a + path_a, b + path_b, shared + path_shared)
+        },
+    );
+    writeln!(
+        writer,
+        "# Shared\tPath name\tBreakpointsA\tBreakpointsB\tShared\tFractionA\tFractionB"
+    )?;
+    for (path_name, breakpoints_a, breakpoints_b, shared) in counts.iter().chain([&total]) {
+        writeln!(
+            writer,
+            "# Shared\t{}\t{}\t{}\t{}\t{:.6}\t{:.6}",
+            path_name,
+            breakpoints_a,
+            breakpoints_b,
+            shared,
+            fraction(*shared, *breakpoints_a),
+            fraction(*shared, *breakpoints_b)
+        )?;
+    }
+    Ok(())
+}
+
+fn fraction(shared: u64, breakpoints: u64) -> f64 {
+    /*
+    Fraction of the breakpoints of a graph that are shared, 1 if the graph has no breakpoint
+    */
+    if breakpoints == 0 {
+        1.0
+    } else {
+        shared as f64 / breakpoints as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segmentation(lengths: &[u64]) -> PathSegmentation {
+        PathSegmentation {
+            path_name: "x".to_string(),
+            nodes: lengths
+                .iter()
+                .enumerate()
+                .map(|(index, &length)| (index.to_string(), '+', length))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_coarsening() {
+        // A cuts the path at 2, 4 and 5, B cuts it at 3, 4 and 6
+        assert_eq!(
+            coarsening(&segmentation(&[2, 2, 1, 2]), &segmentation(&[3, 1, 2, 1])),
+            vec![4]
+        );
+        assert_eq!(
+            coarsening(&segmentation(&[5]), &segmentation(&[2, 3])),
+            Vec::<u64>::new()
+        );
+        assert_eq!(fraction(0, 0), 1.0);
+    }
+
+    #[test]
+    fn test_write_summary() {
+        let mut output: Vec<u8> = Vec::new();
+        write_summary(
+            &[("x".to_string(), 3, 3, 1), ("y".to_string(), 1, 0, 0)],
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Shared\tPath name\tBreakpointsA\tBreakpointsB\tShared\tFractionA\tFractionB\n\
+             # Shared\tx\t3\t3\t1\t0.333333\t0.333333\n\
+             # Shared\ty\t1\t0\t0\t0.000000\t1.000000\n\
+             # Shared\t*\t4\t3\t1\t0.250000\t0.333333\n"
+        );
+    }
+}
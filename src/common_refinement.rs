@@ -0,0 +1,430 @@
+use crate::compute_distance::path_intersection;
+use crate::evaluate_spuriousness::flip;
+use crate::index_gfa_file::{read_sequences, GfaIndex};
+use crate::read_gfa_file::node_offset;
+use crate::segment_paths::PathSegmentation;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, Write};
+// The common refinement of two graphs cuts each shared path at the breakpoints of both graphs:
+// each of its nodes is a maximal interval of a path without a breakpoint in either graph, so it lies inside one node of each graph.
+// Intervals covering the same part of the same nodes of both graphs, in the same relative orientation, are the same refined node,
+// so that paths going through the same nodes of both graphs go through the same refined nodes.
+// A path cuts the nodes it goes through at its breakpoints, and these cuts apply to every path going through the same nodes:
+// cuts are propagated between paths until none is added, so that all paths share one partition of each node of both graphs,
+// and two refined nodes on the same node are either the same interval of this node or disjoint.
+// Refined nodes are oriented as their node of the first graph, whose sequence they hold.
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RefinedNode {
+    // Node of the first graph, and offset of the refined node on its forward strand
+    pub node_a: String,
+    pub offset_a: u64,
+    // Node of the second graph, and offset of the refined node on its forward strand
+    pub node_b: String,
+    pub offset_b: u64,
+    pub length: u64,
+    // True if the node of the second graph is traversed in the opposite orientation to the node of the first graph
+    pub reversed: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommonRefinement {
+    // Refined nodes, the ID of a node being its index plus one
+    pub nodes: Vec<RefinedNode>,
+    ids: HashMap<RefinedNode, u64>,
+    // Path names and their steps: refined node IDs and orientations
+    pub paths: Vec<(String, Vec<(u64, char)>)>,
+    // Paths that could not be refined: name, length in the first graph, length in the second graph
+    pub skipped_paths: Vec<(String, u64, u64)>,
+    // Node names of each graph as keys and the offsets on their forward strand where they are cut as values
+    cuts_a: HashMap<String, BTreeSet<u64>>,
+    cuts_b: HashMap<String, BTreeSet<u64>>,
+}
+
+impl CommonRefinement {
+    pub fn new(paths: &[(PathSegmentation, PathSegmentation)]) -> CommonRefinement {
+        /*
+        Builds the common refinement of paths
+
+        Arguments:
+        - paths: the segmentation of each path in the first and in the second graph, of the same length
+
+        Returns:
+        - the refinement, with the paths in the given order
+        */
+        let mut refinement: CommonRefinement = CommonRefinement::default();
+        // Cutting a node for a path may cut a node of the other graph for another path, we go on until no cut is added
+        let mut changed: bool = true;
+        while changed {
+            changed = false;
+            for (segmentation_a, segmentation_b) in paths {
+                changed |= refinement.add_cuts(segmentation_a, segmentation_b);
+            }
+        }
+        for (segmentation_a, segmentation_b) in paths {
+            refinement.add_path(segmentation_a, segmentation_b);
+        }
+        refinement
+    }
+
+    pub fn add_cuts(
+        &mut self,
+        segmentation_a: &PathSegmentation,
+        segmentation_b: &PathSegmentation,
+    ) -> bool {
+        /*
+        Cuts the nodes a path goes through at the breakpoints of both graphs, and at the cuts already known on its nodes
+
+        Arguments:
+        - segmentation_a: the segmentation of the path in the first graph
+        - segmentation_b: the segmentation of the path in the second graph, of the same length
+
+        Returns:
+        - true if a node was cut at a new offset
+        */
+        let mut changed: bool = false;
+        for (node, _) in self.pieces(segmentation_a, segmentation_b) {
+            for (name, offset, cuts) in [
+                (node.node_a, node.offset_a, &mut self.cuts_a),
+                (node.node_b, node.offset_b, &mut self.cuts_b),
+            ] {
+                let cuts: &mut BTreeSet<u64> = cuts.entry(name).or_default();
+                changed |= cuts.insert(offset);
+                changed |= cuts.insert(offset + node.length);
+            }
+        }
+        changed
+    }
+
+    pub fn add_path(
+        &mut self,
+        segmentation_a: &PathSegmentation,
+        segmentation_b: &PathSegmentation,
+    ) {
+        /*
+        Cuts a path at the breakpoints of both graphs and at the cuts of its nodes, and adds its refined nodes
+        Cuts of every path should be added first, so that paths are cut in the same way
+
+        Arguments:
+        - segmentation_a: the segmentation of the path in the first graph
+        - segmentation_b: the segmentation of the path in the second graph, of the same length
+        */
+        let mut steps: Vec<(u64, char)> = Vec::new();
+        for (node, strand) in self.pieces(segmentation_a, segmentation_b) {
+            let id: u64 = match self.ids.get(&node) {
+                Some(&id) => id,
+                None => {
+                    self.nodes.push(node.clone());
+                    self.ids.insert(node, self.nodes.len() as u64);
+                    self.nodes.len() as u64
+                }
+            };
+            steps.push((id, strand));
+        }
+        self.paths.push((segmentation_a.path_name.clone(), steps));
+    }
+
+    fn pieces(
+        &self,
+        segmentation_a: &PathSegmentation,
+        segmentation_b: &PathSegmentation,
+    ) -> Vec<(RefinedNode, char)> {
+        /*
+        Cuts a path at the breakpoints of both graphs and at the cuts of its nodes
+
+        Arguments:
+        - segmentation_a: the segmentation of the path in the first graph
+        - segmentation_b: the segmentation of the path in the second graph, of the same length
+
+        Returns:
+        - the refined nodes of the path, in its order, with their orientation in the path
+        */
+        let breakpoints_a: Vec<u64> = segmentation_a.breakpoints();
+        let breakpoints_b: Vec<u64> = segmentation_b.breakpoints();
+        let mut pieces: Vec<(RefinedNode, char)> = Vec::new();
+        let (mut i, mut j): (usize, usize) = (0, 0);
+        let mut position: u64 = 0;
+        while i < breakpoints_a.len() && j < breakpoints_b.len() {
+            // The interval ends at the next breakpoint of either graph
+            let next: u64 = breakpoints_a[i].min(breakpoints_b[j]);
+            if next > position {
+                let (node_a, strand_a, length_a) = &segmentation_a.nodes[i];
+                let (node_b, strand_b, length_b) = &segmentation_b.nodes[j];
+                let offsets_a: (u64, u64) = (
+                    node_offset(position, breakpoints_a[i], *length_a, *strand_a),
+                    node_offset(next, breakpoints_a[i], *length_a, *strand_a),
+                );
+                let offsets_b: (u64, u64) = (
+                    node_offset(position, breakpoints_b[j], *length_b, *strand_b),
+                    node_offset(next, breakpoints_b[j], *length_b, *strand_b),
+                );
+                let (start_a, start_b): (u64, u64) =
+                    (offsets_a.0.min(offsets_a.1), offsets_b.0.min(offsets_b.1));
+                let length: u64 = next - position;
+                // Offsets inside the interval, in the direction of the path, where one of its nodes is cut
+                let mut offsets: BTreeSet<u64> = BTreeSet::from([0, length]);
+                for (node, start, strand, cuts) in [
+                    (node_a, start_a, *strand_a, &self.cuts_a),
+                    (node_b, start_b, *strand_b, &self.cuts_b),
+                ] {
+                    if let Some(cuts) = cuts.get(node) {
+                        for &cut in cuts.range(start + 1..start + length) {
+                            offsets.insert(if strand == '+' {
+                                cut - start
+                            } else {
+                                start + length - cut
+                            });
+                        }
+                    }
+                }
+                let offsets: Vec<u64> = offsets.into_iter().collect();
+                for pair in offsets.windows(2) {
+                    let (from, to): (u64, u64) = (pair[0], pair[1]);
+                    // Offsets on the forward strand of the nodes
+                    let forward = |start: u64, strand: char| -> u64 {
+                        if strand == '+' {
+                            start + from
+                        } else {
+                            start + length - to
+                        }
+                    };
+                    pieces.push((
+                        RefinedNode {
+                            node_a: node_a.clone(),
+                            offset_a: forward(start_a, *strand_a),
+                            node_b: node_b.clone(),
+                            offset_b: forward(start_b, *strand_b),
+                            length: to - from,
+                            reversed: strand_a != strand_b,
+                        },
+                        *strand_a,
+                    ));
+                }
+            }
+            // We move forward on the graphs whose node ends here
+            if breakpoints_a[i] == next {
+                i += 1;
+            }
+            if breakpoints_b[j] == next {
+                j += 1;
+            }
+            position = next;
+        }
+        pieces
+    }
+
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        sequences: &HashMap<String, String>,
+    ) -> io::Result<()> {
+        /*
+        Writes the refinement as GFA1: segments, links between consecutive nodes of paths, and paths
+        Each segment is tagged with its node of each graph and its offset on the forward strand of this node:
+        na:Z: and oa:i: for the first graph, nb:Z: and ob:i: for the second graph
+
+        Arguments:
+        - writer: where the GFA is written
+        - sequences: the sequences of the nodes of the first graph, refined nodes have '*' as sequence if missing
+        */
+        writeln!(writer, "H\tVN:Z:1.0")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let sequence: &str = match sequences.get(&node.node_a) {
+                Some(sequence) if sequence != "*" => sequence
+                    .get(node.offset_a as usize..(node.offset_a + node.length) as usize)
+                    .unwrap_or("*"),
+                _ => "*",
+            };
+            writeln!(
+                writer,
+                "S\t{}\t{}\tLN:i:{}\tna:Z:{}\toa:i:{}\tnb:Z:{}\tob:i:{}",
+                index + 1,
+                sequence,
+                node.length,
+                node.node_a,
+                node.offset_a,
+                node.node_b,
+                node.offset_b
+            )?;
+        }
+        // A link and its reverse complement are the same link, we keep the smallest one
+        let mut links: BTreeSet<(u64, char, u64, char)> = BTreeSet::new();
+        for (_, steps) in self.paths.iter() {
+            for pair in steps.windows(2) {
+                let ((from, from_strand), (to, to_strand)) = (pair[0], pair[1]);
+                links.insert((from, from_strand, to, to_strand).min((
+                    to,
+                    flip(to_strand),
+                    from,
+                    flip(from_strand),
+                )));
+            }
+        }
+        for (from, from_strand, to, to_strand) in links {
+            writeln!(
+                writer,
+                "L\t{}\t{}\t{}\t{}\t0M",
+                from, from_strand, to, to_strand
+            )?;
+        }
+        for (path_name, steps) in self.paths.iter() {
+            let steps: Vec<String> = steps
+                .iter()
+                .map(|(id, strand)| format!("{}{}", id, strand))
+                .collect();
+            writeln!(writer, "P\t{}\t{}\t*", path_name, steps.join(","))?;
+        }
+        writer.flush()
+    }
+}
+
+pub fn refine(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
+) -> io::Result<CommonRefinement> {
+    /*
+    Computes the common refinement of the paths found in both graphs
+
+    Arguments:
+    - file_path1: the path to the first GFA file
+    - file_path2: the path to the second GFA file
+    - index1: the index of the first GFA file
+    - index2: the index of the second GFA file
+
+    Output:
+    - Writes to standard output the refinement as GFA, with the sequences of the first graph
+
+    Returns:
+    - the refinement, with the paths left out as they have different lengths in the two graphs
+    */
+    let mut paths: Vec<(PathSegmentation, PathSegmentation)> = Vec::new();
+    let mut skipped_paths: Vec<(String, u64, u64)> = Vec::new();
+    for path_name in path_intersection(index1, index2) {
+        let length1: u64 = index1.path_lengths[&path_name];
+        let length2: u64 = index2.path_lengths[&path_name];
+        if length1 != length2 {
+            skipped_paths.push((path_name, length1, length2));
+            continue;
+        }
+        paths.push((
+            PathSegmentation::read(file_path1, index1, &path_name)?,
+            PathSegmentation::read(file_path2, index2, &path_name)?,
+        ));
+    }
+    let mut refinement: CommonRefinement = CommonRefinement::new(&paths);
+    refinement.skipped_paths = skipped_paths;
+    let node_names: HashSet<String> = refinement
+        .nodes
+        .iter()
+        .map(|node| node.node_a.clone())
+        .collect();
+    let sequences: HashMap<String, String> = read_sequences(file_path1, &node_names)?;
+    refinement.write(&mut io::BufWriter::new(io::stdout()), &sequences)?;
+    Ok(refinement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segmentation(nodes: &[(&str, char, u64)]) -> PathSegmentation {
+        PathSegmentation {
+            path_name: "x".to_string(),
+            nodes: nodes
+                .iter()
+                .map(|&(name, strand, length)| (name.to_string(), strand, length))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_common_refinement() {
+        // A cuts the path at 2 and 4, B cuts it at 3 and 4, then the path goes back through the first nodes
+        let refinement: CommonRefinement = CommonRefinement::new(&[
+            (
+                segmentation(&[("1", '+', 2), ("2", '-', 2), ("1", '+', 2)]),
+                segmentation(&[("4", '+', 3), ("5", '+', 1), ("6", '+', 2)]),
+            ),
+            (
+                segmentation(&[("1", '+', 2), ("3", '+', 1)]),
+                segmentation(&[("4", '+', 3)]),
+            ),
+        ]);
+        // The second half of node 2 comes first, as it is traversed in reverse
+        assert_eq!(refinement.nodes.len(), 5);
+        assert_eq!(
+            refinement.nodes[1],
+            RefinedNode {
+                node_a: "2".to_string(),
+                offset_a: 1,
+                node_b: "4".to_string(),
+                offset_b: 2,
+                length: 1,
+                reversed: true,
+            }
+        );
+        assert_eq!(
+            refinement.paths[0].1,
+            vec![(1, '+'), (2, '-'), (3, '-'), (4, '+')]
+        );
+        // The same part of nodes 1 and 4 is the same refined node
+        assert_eq!(refinement.paths[1].1, vec![(1, '+'), (5, '+')]);
+
+        let sequences: HashMap<String, String> = HashMap::from([
+            ("1".to_string(), "AC".to_string()),
+            ("2".to_string(), "GT".to_string()),
+        ]);
+        let mut output: Vec<u8> = Vec::new();
+        refinement.write(&mut output, &sequences).unwrap();
+        let output: String = String::from_utf8(output).unwrap();
+        assert!(output.contains("S\t2\tT\tLN:i:1\tna:Z:2\toa:i:1\tnb:Z:4\tob:i:2\n"));
+        assert!(output.contains("L\t1\t+\t2\t-\t0M\n"));
+        assert!(output.ends_with("P\tx\t1+,2-,3-,4+\t*\nP\tx\t1+,5+\t*\n"));
+    }
+
+    #[test]
+    fn test_common_refinement_shared_cuts() {
+        // x and y go through node 1 of A, cut at 2 by B for y only, z goes through node 10 of B as x does
+        let refinement: CommonRefinement = CommonRefinement::new(&[
+            (
+                segmentation(&[("1", '+', 3)]),
+                segmentation(&[("10", '+', 3)]),
+            ),
+            (
+                segmentation(&[("1", '+', 3)]),
+                segmentation(&[("20", '+', 2), ("21", '+', 1)]),
+            ),
+            (
+                segmentation(&[("5", '-', 3)]),
+                segmentation(&[("10", '+', 3)]),
+            ),
+        ]);
+        // Every path is cut at 2 on node 1, and the cut goes on to node 10 and to node 5
+        let intervals = |node: &str| -> BTreeSet<(u64, u64)> {
+            refinement
+                .nodes
+                .iter()
+                .filter(|refined| refined.node_a == node)
+                .map(|refined| (refined.offset_a, refined.length))
+                .collect()
+        };
+        assert_eq!(intervals("1"), BTreeSet::from([(0, 2), (2, 1)]));
+        assert_eq!(intervals("5"), BTreeSet::from([(1, 2), (0, 1)]));
+        assert_eq!(refinement.paths[0].1, vec![(1, '+'), (2, '+')]);
+        assert_eq!(refinement.paths[1].1, vec![(3, '+'), (4, '+')]);
+        assert_eq!(refinement.paths[2].1, vec![(5, '-'), (6, '-')]);
+        assert_eq!(
+            refinement.nodes[5],
+            RefinedNode {
+                node_a: "5".to_string(),
+                offset_a: 0,
+                node_b: "10".to_string(),
+                offset_b: 2,
+                length: 1,
+                reversed: true,
+            }
+        );
+    }
+}
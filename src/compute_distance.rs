@@ -1,211 +1,1395 @@
+use crate::edit_density::EditDensity;
+use crate::evaluate_spuriousness::Junction;
+use crate::index_gfa_file::GfaIndex;
+use crate::read_gfa_file::{node_offset, PathReader};
+use crate::select_regions::{contains, covered_length, intersect, Regions};
+use crate::write_report::{OutputFormat, ReportWriter};
 use std::cmp::min;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Condvar, Mutex, MutexGuard};
+use std::thread;
+// The distance between two graphs is computed path by path: both versions of a path are read node by node,
+// and every position where one graph has a breakpoint and the other has not is an edit.
+// Edits of one path are given by the PathEdits iterator, edits of all shared paths by the EditIterator.
 
-pub fn distance(
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    // A breakpoint of the first graph, missing in the second graph
+    Split,
+    // A breakpoint of the second graph, missing in the first graph
+    Merge,
+}
+
+impl Operation {
+    pub fn as_char(&self) -> char {
+        match self {
+            Operation::Split => 'S',
+            Operation::Merge => 'M',
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    // Name of the path where the edit occurs
+    pub path: String,
+    // Position of the edit on the path
+    pub position: u64,
+    pub op: Operation,
+    // Nodes of each graph where the edit occurs
+    pub node_a: String,
+    pub node_b: String,
+    // Positions on the path where node_a and node_b end
+    pub breakpoint_a: u64,
+    pub breakpoint_b: u64,
+    // Orientations of node_a and node_b in the path ('+' or '-')
+    pub strand_a: char,
+    pub strand_b: char,
+    // Offsets of the edit inside node_a and node_b, on the forward strand of the nodes
+    pub offset_a: u64,
+    pub offset_b: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpuriousBreakpoint {
+    // Name of the path where the breakpoint occurs
+    pub path: String,
+    // Position of the breakpoint on the path
+    pub position: u64,
+    // Operation the breakpoint would have been: a split in the first graph, a merge in the second graph
+    pub op: Operation,
+    // Nodes before and after the breakpoint in the path, with their orientations
+    pub junction: Junction,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EditCounts {
+    pub equivalences: u64,
+    pub splits: u64,
+    pub merges: u64,
+    pub spurious: u64,
+    // Edits dropped as they fall inside the mask
+    pub masked: u64,
+    // Breakpoints found at the same position in both graphs, path ends excluded
+    pub shared: u64,
+    // Number of nodes read in the paths of each graph
+    pub nodes_a: u64,
+    pub nodes_b: u64,
+}
+
+impl EditCounts {
+    pub fn distance(&self) -> u64 {
+        self.splits + self.merges
+    }
+
+    pub fn add(&mut self, other: &EditCounts) {
+        self.equivalences += other.equivalences;
+        self.splits += other.splits;
+        self.merges += other.merges;
+        self.spurious += other.spurious;
+        self.masked += other.masked;
+        self.shared += other.shared;
+        self.nodes_a += other.nodes_a;
+        self.nodes_b += other.nodes_b;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathSummary {
+    pub path_name: String,
+    pub length: u64,
+    pub counts: EditCounts,
+}
+
+impl PathSummary {
+    pub fn distance_per_base(&self) -> f64 {
+        /*
+        Returns the distance divided by the length of the path
+        */
+        ratio(self.counts.distance(), self.length)
+    }
+
+    pub fn distance_per_node(&self) -> f64 {
+        /*
+        Returns the distance divided by the number of nodes of the path in both graphs
+        Each edit is a node boundary found in one graph only, so the result is between 0 and 1
+        */
+        ratio(
+            self.counts.distance(),
+            self.counts.nodes_a + self.counts.nodes_b,
+        )
+    }
+
+    // Breakpoints are compared as sets of positions, spurious breakpoints excluded:
+    // A holds shared breakpoints and splits, B holds shared breakpoints and merges
+
+    pub fn jaccard(&self) -> f64 {
+        /*
+        Returns the Jaccard index of the breakpoints of both graphs, 1 if neither graph has breakpoints
+        */
+        let union: u64 = self.counts.shared + self.counts.distance();
+        ratio_or(self.counts.shared, union, 1.0)
+    }
+
+    pub fn distance_per_breakpoint(&self) -> f64 {
+        /*
+        Returns the distance divided by the number of breakpoints found in either graph (1 - Jaccard index)
+        */
+        let union: u64 = self.counts.shared + self.counts.distance();
+        ratio(self.counts.distance(), union)
+    }
+
+    pub fn precision(&self) -> f64 {
+        /*
+        Returns the fraction of breakpoints of A also found in B
+        */
+        ratio_or(
+            self.counts.shared,
+            self.counts.shared + self.counts.splits,
+            1.0,
+        )
+    }
+
+    pub fn recall(&self) -> f64 {
+        /*
+        Returns the fraction of breakpoints of B also found in A
+        */
+        ratio_or(
+            self.counts.shared,
+            self.counts.shared + self.counts.merges,
+            1.0,
+        )
+    }
+
+    pub fn f1(&self) -> f64 {
+        /*
+        Returns the harmonic mean of precision and recall
+        */
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+}
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    ratio_or(numerator, denominator, 0.0)
+}
+
+fn ratio_or(numerator: u64, denominator: u64, default: f64) -> f64 {
+    if denominator == 0 {
+        default
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DistanceReport {
+    // Paths found in both graphs
+    pub intersection: Vec<String>,
+    // Paths that could not be compared: name, length in the first graph, length in the second graph
+    pub skipped_paths: Vec<(String, u64, u64)>,
+    // Counts of operations of each compared path, in the order of the intersection
+    pub paths: Vec<PathSummary>,
+    // Counts of operations over all compared paths
+    pub counts: EditCounts,
+}
+
+impl DistanceReport {
+    pub fn distance(&self) -> u64 {
+        self.counts.distance()
+    }
+
+    pub fn summary(&self) -> PathSummary {
+        /*
+        Returns the counts over all compared paths, as a path named '*' whose length is the sum of their lengths
+        */
+        PathSummary {
+            path_name: "*".to_string(),
+            length: self.paths.iter().map(|path| path.length).sum(),
+            counts: self.counts,
+        }
+    }
+}
+
+pub fn path_intersection(index1: &GfaIndex, index2: &GfaIndex) -> Vec<String> {
+    /*
+    Returns the names of the paths found in both graphs, sorted by name so that the output does not change between runs
+    */
+    let mut intersection: Vec<String> = index1
+        .path_positions
+        .keys()
+        .filter(|&k| index2.path_positions.contains_key(k))
+        .cloned()
+        .collect();
+    intersection.sort();
+    intersection
+}
+
+pub struct PathEdits<'a> {
+    path_name: String,
+    file1: PathReader,
+    file2: PathReader,
+    node_sizes1: &'a HashMap<String, u64>,
+    node_sizes2: &'a HashMap<String, u64>,
+    // Spurious junctions of each graph, in both directions of traversal
+    sp1: &'a HashSet<Junction>,
+    sp2: &'a HashSet<Junction>,
+    // Current node names, orientations and lengths
+    node1: String,
+    node2: String,
+    orientation1: char,
+    orientation2: char,
+    length1: u64,
+    length2: u64,
+    breakpoint_a: u64,
+    breakpoint_b: u64,
+    position: u64,
+    max_length: u64,
+    // Intervals of the path where edits are reported, the whole path if None
+    regions: Option<Vec<(u64, u64)>>,
+    // Intervals of the path where edits are dropped, neither reported nor counted
+    mask: Vec<(u64, u64)>,
+    // Position where the walk stops, after the last selected interval
+    stop: u64,
+    // Counts of operations, complete once the iterator is exhausted
+    pub counts: EditCounts,
+    // Spurious breakpoints not reported as edits, in the selected regions and outside of the mask
+    pub spurious: Vec<SpuriousBreakpoint>,
+}
+
+pub fn path_edits<'a>(
     file_path1: &str,
     file_path2: &str,
-    node_sizes1: HashMap<String, u64>,
-    node_sizes2: HashMap<String, u64>,
-    path_positions1: HashMap<String, u64>,
-    path_positions2: HashMap<String, u64>,
-    path_lengths1: HashMap<String, u64>,
-    path_lengths2: HashMap<String, u64>,
-    path_types1: HashMap<String, char>,
-    path_types2: HashMap<String, char>,
-    spurious_breakpoints1: Vec<String>,
-    spurious_breakpoints2: Vec<String>,
-) -> io::Result<()> {
+    index1: &'a GfaIndex,
+    index2: &'a GfaIndex,
+    path_name: &str,
+    spurious_breakpoints1: &'a HashSet<Junction>,
+    spurious_breakpoints2: &'a HashSet<Junction>,
+) -> io::Result<PathEdits<'a>> {
     /*
-    Given two GFA files and their associated node sizes and path positions, this function computes the distance between the two graphs.
+    Starts the comparison of a path between two graphs
 
     Arguments:
     - file_path1: the path to the first GFA file
     - file_path2: the path to the second GFA file
-    - node_sizes1: a HashMap with the node names as keys and the node sizes as values for the first GFA file
-    - node_sizes2: a HashMap with the node names as keys and the node sizes as values for the second GFA file
-    - path_positions1: a HashMap with the path names as keys and the offset of the path description for the first GFA file
-    - path_positions2: a HashMap with the path names as keys and the offset of the path description for the second GFA file
-    - path_lengths1: a HashMap with the path names as keys and the number of nodes in the path for the first GFA file
-    - path_lengths2: a HashMap with the path names as keys and the number of nodes in the path for the second GFA file
-    - path_types1: a HashMap with the path names as keys and the path types (W or P) as values for the first GFA file
-    - path_types2: a HashMap with the path names as keys and the path types (W or P) as values for the second GFA file
-    - spurious_breakpoints1: a vector of spurious node IDs for the first GFA file
-    - spurious_breakpoints2: a vector of spurious node IDs for the second GFA file
+    - index1: the index of the first GFA file
+    - index2: the index of the second GFA file
+    - path_name: the name of the path, found in both indexes
+    - spurious_breakpoints1: spurious junctions of the first GFA file
+    - spurious_breakpoints2: spurious junctions of the second GFA file
 
-    Ouptut:
-    - Writes to standard output the operations (merges and splits) needed to transform the first graph into the second graph
+    Returns:
+    - an iterator over the edits of the path
+    - an error if the two paths have different lengths, as they cannot be compared
     */
-    let intersection: Vec<&String> = path_positions1
-        .keys()
-        .filter(|&k| path_positions2.contains_key(k))
-        .collect();
+    let max_length1: u64 = index1.path_lengths[path_name];
+    let max_length2: u64 = index2.path_lengths[path_name];
+    if max_length1 != max_length2 {
+        return Err(length_error(path_name, max_length1, max_length2));
+    }
+    // We open the two files at the positions of the path
+    let file1: PathReader = PathReader::open(
+        file_path1,
+        &index1.path_positions[path_name],
+        index1.path_types[path_name],
+    )?;
+    let file2: PathReader = PathReader::open(
+        file_path2,
+        &index2.path_positions[path_name],
+        index2.path_types[path_name],
+    )?;
+    Ok(PathEdits {
+        path_name: path_name.to_string(),
+        file1,
+        file2,
+        node_sizes1: &index1.seq_lengths,
+        node_sizes2: &index2.seq_lengths,
+        sp1: spurious_breakpoints1,
+        sp2: spurious_breakpoints2,
+        node1: String::new(),
+        node2: String::new(),
+        orientation1: '+',
+        orientation2: '+',
+        length1: 0,
+        length2: 0,
+        breakpoint_a: 0,
+        breakpoint_b: 0,
+        position: 0,
+        max_length: max_length1,
+        regions: None,
+        mask: Vec::new(),
+        stop: max_length1,
+        counts: EditCounts::default(),
+        spurious: Vec::new(),
+    })
+}
 
-    println!("# Intersection of paths: {:?}", intersection);
+impl PathEdits<'_> {
+    pub fn restrict(mut self, intervals: &[(u64, u64)]) -> Self {
+        /*
+        Restricts the comparison to sorted, non-overlapping intervals of the path
+        Only edits whose position falls inside an interval are returned and counted
+        */
+        self.stop = intervals
+            .last()
+            .map_or(0, |&(_, end)| end)
+            .min(self.max_length);
+        self.regions = Some(intervals.to_vec());
+        self
+    }
 
-    // Print the length of every path in the intersection
-    for path_name in intersection.iter() {
-        println!("## {}\t{}", path_name, path_lengths1[path_name.as_str()]);
+    pub fn mask(mut self, intervals: &[(u64, u64)]) -> Self {
+        /*
+        Masks sorted, non-overlapping intervals of the path
+        Edits whose position falls inside an interval are not returned, and only counted as masked
+        */
+        self.mask = intervals.to_vec();
+        self
     }
 
-    let mut equivalences_count: i32 = 0;
-    let mut merges_count: i32 = 0;
-    let mut splits_count: i32 = 0;
-    let mut spurious_count: i32 = 0;
+    pub fn summary(&self) -> PathSummary {
+        /*
+        Returns the counts of operations of the path, complete once the iterator is exhausted
+        The length of the path is the number of positions compared, masked positions excluded
+        */
+        let selected: Vec<(u64, u64)> = match &self.regions {
+            Some(intervals) => intervals.clone(),
+            None => vec![(0, self.max_length)],
+        };
+        PathSummary {
+            path_name: self.path_name.clone(),
+            length: covered_length(&selected, self.max_length)
+                - covered_length(&intersect(&selected, &self.mask), self.max_length),
+            counts: self.counts,
+        }
+    }
 
-    // We need to duplicate the spurius vectors to keep the original ones
-    let mut sp1: Vec<String> = spurious_breakpoints1.clone();
-    let mut sp2: Vec<String> = spurious_breakpoints2.clone();
+    fn is_selected(&self, position: u64) -> bool {
+        match &self.regions {
+            Some(intervals) => contains(intervals, position),
+            None => true,
+        }
+    }
 
-    println!("# Path name\tPosition\tOperation\tNodeA\tNodeB\tBreakpointA\tBreakpointB");
-    for path_name in intersection {
-        // We get the positions of the path in the two files
-        let pos1: u64 = path_positions1[path_name];
-        let pos2: u64 = path_positions2[path_name];
+    fn edit(&self, op: Operation) -> Edit {
+        /*
+        Builds the edit found at the current position, between the current nodes
+        */
+        Edit {
+            path: self.path_name.clone(),
+            position: self.position,
+            op,
+            node_a: self.node1.clone(),
+            node_b: self.node2.clone(),
+            breakpoint_a: self.breakpoint_a,
+            breakpoint_b: self.breakpoint_b,
+            strand_a: self.orientation1,
+            strand_b: self.orientation2,
+            offset_a: node_offset(
+                self.position,
+                self.breakpoint_a,
+                self.length1,
+                self.orientation1,
+            ),
+            offset_b: node_offset(
+                self.position,
+                self.breakpoint_b,
+                self.length2,
+                self.orientation2,
+            ),
+        }
+    }
 
-        // We open the two files
-        let mut file1: BufReader<File> = BufReader::new(File::open(file_path1)?);
-        let mut file2: BufReader<File> = BufReader::new(File::open(file_path2)?);
+    fn spurious_breakpoint(&self, op: Operation, previous: (String, char)) -> SpuriousBreakpoint {
+        /*
+        Builds the spurious breakpoint found at the current position, between the previous node and the current node of the graph
+        */
+        let (node, orientation): (&String, char) = match op {
+            Operation::Split => (&self.node1, self.orientation1),
+            Operation::Merge => (&self.node2, self.orientation2),
+        };
+        SpuriousBreakpoint {
+            path: self.path_name.clone(),
+            position: self.position,
+            op,
+            junction: (previous.0, previous.1, node.clone(), orientation),
+        }
+    }
 
-        // We seek to the position of the path in the two files
-        file1.seek(SeekFrom::Start(pos1))?;
-        file2.seek(SeekFrom::Start(pos2))?;
+    fn step(&mut self) -> io::Result<Option<Edit>> {
+        /*
+        Moves forward on the path to the next breakpoint of either graph
+        Operations found outside of the selected regions, or inside the mask, are neither counted nor returned
 
-        // Buffer to read the two files
-        let mut buffer1: [u8; 1] = [0; 1];
-        let mut buffer2: [u8; 1] = [0; 1];
+        Returns:
+        - the edit found at the current position, if any
+        */
+        let mut edit: Option<Edit> = None;
+        let mut spurious: Option<SpuriousBreakpoint> = None;
+        // Operations found at this position, only counted if the position is selected
+        let mut counts: EditCounts = EditCounts::default();
+        if self.breakpoint_a == self.breakpoint_b {
+            // The two positions in the two paths are aligned
+            counts.equivalences += 1;
+            if self.position > 0 {
+                counts.shared += 1;
+            }
+            // No edition operation is needed
+            // We must read the two next nodes in the two files
+            (self.node1, self.orientation1, self.length1) =
+                self.file1.expect_next_segment(self.node_sizes1)?;
+            (self.node2, self.orientation2, self.length2) =
+                self.file2.expect_next_segment(self.node_sizes2)?;
+            // Store their associated sizes in breakpoint_a and breakpoint_b
+            self.breakpoint_a += self.length1;
+            self.breakpoint_b += self.length2;
+            counts.nodes_a += 1;
+            counts.nodes_b += 1;
+        } else if self.breakpoint_a < self.breakpoint_b {
+            // The node in the first path is missing in the second path
+            // The two positions in the two paths are not aligned
+            let split: Edit = self.edit(Operation::Split);
+            let previous: (String, char) = (self.node1.clone(), self.orientation1);
+            (self.node1, self.orientation1, self.length1) =
+                self.file1.expect_next_segment(self.node_sizes1)?;
+            self.breakpoint_a += self.length1;
+            counts.nodes_a += 1;
+            if is_spurious(self.sp1, &previous, &self.node1, self.orientation1) {
+                // The breakpoint of the first graph joins two nodes that could be merged
+                counts.spurious += 1;
+                spurious = Some(self.spurious_breakpoint(Operation::Split, previous));
+            } else {
+                // It is a split operation
+                counts.splits += 1;
+                edit = Some(split);
+            }
+        } else {
+            // The node in the second path is missing in the first path
+            // The two positions in the two paths are not aligned
+            let merge: Edit = self.edit(Operation::Merge);
+            let previous: (String, char) = (self.node2.clone(), self.orientation2);
+            (self.node2, self.orientation2, self.length2) =
+                self.file2.expect_next_segment(self.node_sizes2)?;
+            self.breakpoint_b += self.length2;
+            counts.nodes_b += 1;
+            if is_spurious(self.sp2, &previous, &self.node2, self.orientation2) {
+                // The breakpoint of the second graph joins two nodes that could be merged
+                counts.spurious += 1;
+                spurious = Some(self.spurious_breakpoint(Operation::Merge, previous));
+            } else {
+                // It is a merge operation
+                counts.merges += 1;
+                edit = Some(merge);
+            }
+        }
 
-        // Node names
-        let mut node1: String = String::new();
-        let mut node2: String = String::new();
+        if !self.is_selected(self.position) {
+            edit = None;
+        } else if contains(&self.mask, self.position) {
+            // The edit is dropped, we only keep track of it
+            if edit.take().is_some() {
+                self.counts.masked += 1;
+            }
+        } else {
+            self.counts.add(&counts);
+            self.spurious.extend(spurious);
+        }
+        // We update the position in the two paths
+        self.position = min(self.breakpoint_a, self.breakpoint_b);
+        Ok(edit)
+    }
+}
 
-        let mut breakpoint_a: u64 = 0;
-        let mut breakpoint_b: u64 = 0;
+fn is_spurious(
+    spurious_breakpoints: &HashSet<Junction>,
+    previous: &(String, char),
+    node: &str,
+    orientation: char,
+) -> bool {
+    /*
+    Tells if the junction between the previous node of a path and the current one is spurious
+    */
+    !spurious_breakpoints.is_empty()
+        && spurious_breakpoints.contains(&(
+            previous.0.clone(),
+            previous.1,
+            node.to_string(),
+            orientation,
+        ))
+}
+
+impl Iterator for PathEdits<'_> {
+    type Item = io::Result<Edit>;
+
+    fn next(&mut self) -> Option<io::Result<Edit>> {
+        while self.position < self.stop {
+            match self.step() {
+                Ok(Some(edit)) => return Some(Ok(edit)),
+                Ok(None) => continue,
+                Err(error) => {
+                    // The path can't be read further
+                    self.position = self.stop;
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+}
 
-        let mut position: u64 = 0;
+pub struct EditIterator<'a> {
+    file_path1: &'a str,
+    file_path2: &'a str,
+    index1: &'a GfaIndex,
+    index2: &'a GfaIndex,
+    spurious_breakpoints1: &'a HashSet<Junction>,
+    spurious_breakpoints2: &'a HashSet<Junction>,
+    // Paths still to be compared, and the path being compared
+    paths: std::vec::IntoIter<String>,
+    current: Option<PathEdits<'a>>,
+    report: DistanceReport,
+}
 
-        let max_length1: u64 = path_lengths1[path_name.as_str()] as u64;
-        let max_length2: u64 = path_lengths2[path_name.as_str()] as u64;
+pub fn edits<'a>(
+    file_path1: &'a str,
+    file_path2: &'a str,
+    index1: &'a GfaIndex,
+    index2: &'a GfaIndex,
+    spurious_breakpoints1: &'a HashSet<Junction>,
+    spurious_breakpoints2: &'a HashSet<Junction>,
+) -> EditIterator<'a> {
+    /*
+    Compares every path found in both graphs
 
-        if max_length1 != max_length2 {
-            // The two paths have different lengths, we cannot compare them
-            println!(
-                "# Error: the two paths representing {} have different lengths: {} and {}.",
-                path_name, max_length1, max_length2
-            );
-            continue;
-        } else {
-            while position < max_length1 {
-                if breakpoint_a == breakpoint_b {
-                    // The two positions in the two paths are aligned
-                    equivalences_count += 1;
-                    // No edition operation is needed
-                    // We must read the two next nodes in the two files
-                    node1 = if path_types1[path_name.as_str()] == 'P' {
-                        read_next_p_node(&mut file1, &mut buffer1)
-                    } else {
-                        read_next_w_node(&mut file1, &mut buffer1)
-                    };
-                    node2 = if path_types2[path_name.as_str()] == 'P' {
-                        read_next_p_node(&mut file2, &mut buffer2)
-                    } else {
-                        read_next_w_node(&mut file2, &mut buffer2)
-                    };
-                    // Store their associated sizes in breakpoint_a and breakpoint_b
-                    breakpoint_a += node_sizes1.get(&node1).unwrap().clone();
-                    breakpoint_b += node_sizes2.get(&node2).unwrap().clone();
-                } else if breakpoint_a < breakpoint_b {
-                    // The node in the first path is missing in the second path
-                    // The two positions in the two paths are not aligned
-                    if sp2.contains(&node2) {
-                        // Remove the spurious breakpoint from the vector
-                        sp2.retain(|x| x != &node2);
-                        spurious_count += 1;
-                    } else {
-                        // It is a split operation
-                        splits_count += 1;
-                        println!(
-                            "{}\t{}\tS\t{}\t{}\t{}\t{}",
-                            path_name, position, node1, node2, breakpoint_a, breakpoint_b
-                        );
-                    }
-                    node1 = if path_types1[path_name.as_str()] == 'P' {
-                        read_next_p_node(&mut file1, &mut buffer1)
-                    } else {
-                        read_next_w_node(&mut file1, &mut buffer1)
-                    };
-                    breakpoint_a += node_sizes1.get(&node1).unwrap().clone();
-                } else if breakpoint_a > breakpoint_b {
-                    // The node in the second path is missing in the first path
-                    // The two positions in the two paths are not aligned
-                    if sp1.contains(&node1) {
-                        // Remove the spurious breakpoint from the vector
-                        sp1.retain(|x| x != &node1);
-                        spurious_count += 1;
-                    } else {
-                        // It is a merge operation
-                        merges_count += 1;
-                        println!(
-                            "{}\t{}\tM\t{}\t{}\t{}\t{}",
-                            path_name, position, node1, node2, breakpoint_a, breakpoint_b
-                        );
+    Arguments:
+    - file_path1: the path to the first GFA file
+    - file_path2: the path to the second GFA file
+    - index1: the index of the first GFA file
+    - index2: the index of the second GFA file
+    - spurious_breakpoints1: spurious junctions of the first GFA file
+    - spurious_breakpoints2: spurious junctions of the second GFA file
+
+    Returns:
+    - an iterator over the edits of all paths, path after path; its report is complete once it is exhausted
+    */
+    let intersection: Vec<String> = path_intersection(index1, index2);
+    EditIterator {
+        file_path1,
+        file_path2,
+        index1,
+        index2,
+        spurious_breakpoints1,
+        spurious_breakpoints2,
+        paths: intersection.clone().into_iter(),
+        current: None,
+        report: DistanceReport {
+            intersection,
+            ..DistanceReport::default()
+        },
+    }
+}
+
+impl EditIterator<'_> {
+    pub fn report(&self) -> &DistanceReport {
+        &self.report
+    }
+}
+
+impl Iterator for EditIterator<'_> {
+    type Item = io::Result<Edit>;
+
+    fn next(&mut self) -> Option<io::Result<Edit>> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                if let Some(edit) = current.next() {
+                    return Some(edit);
+                }
+                self.report.counts.add(&current.counts);
+                self.report.paths.push(current.summary());
+                self.current = None;
+            }
+            let path_name: String = self.paths.next()?;
+            let length1: u64 = self.index1.path_lengths[&path_name];
+            let length2: u64 = self.index2.path_lengths[&path_name];
+            if length1 != length2 {
+                // The two paths have different lengths, we cannot compare them
+                self.report
+                    .skipped_paths
+                    .push((path_name, length1, length2));
+                continue;
+            }
+            match path_edits(
+                self.file_path1,
+                self.file_path2,
+                self.index1,
+                self.index2,
+                &path_name,
+                self.spurious_breakpoints1,
+                self.spurious_breakpoints2,
+            ) {
+                Ok(path_edits) => self.current = Some(path_edits),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+pub struct PathComparison {
+    pub path_name: String,
+    // Edits of the path, in path order
+    pub edits: Vec<Edit>,
+    // Spurious breakpoints of the path, in path order
+    pub spurious: Vec<SpuriousBreakpoint>,
+    pub summary: PathSummary,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn compare_path(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
+    path_name: &str,
+    spurious_breakpoints1: &HashSet<Junction>,
+    spurious_breakpoints2: &HashSet<Junction>,
+    options: &DistanceOptions,
+) -> io::Result<PathComparison> {
+    /*
+    Compares a path between two graphs, collecting all its edits
+
+    Arguments:
+    - file_path1, file_path2, index1, index2, path_name, spurious_breakpoints1, spurious_breakpoints2: see path_edits
+    - options: if regions are given, only edits inside the intervals of the path are collected and counted, and edits inside the mask are dropped
+
+    Returns:
+    - the edits of the path and the counts of operations
+    */
+    let mut path_edits: PathEdits = selected_path_edits(
+        file_path1,
+        file_path2,
+        index1,
+        index2,
+        path_name,
+        spurious_breakpoints1,
+        spurious_breakpoints2,
+        options,
+    )?;
+    let edits: Vec<Edit> = path_edits.by_ref().collect::<io::Result<Vec<Edit>>>()?;
+    Ok(PathComparison {
+        path_name: path_name.to_string(),
+        edits,
+        spurious: std::mem::take(&mut path_edits.spurious),
+        summary: path_edits.summary(),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn selected_path_edits<'a>(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &'a GfaIndex,
+    index2: &'a GfaIndex,
+    path_name: &str,
+    spurious_breakpoints1: &'a HashSet<Junction>,
+    spurious_breakpoints2: &'a HashSet<Junction>,
+    options: &DistanceOptions,
+) -> io::Result<PathEdits<'a>> {
+    /*
+    Starts the comparison of a path, restricted to the regions and outside of the mask given in the options
+    */
+    let mut path_edits: PathEdits = path_edits(
+        file_path1,
+        file_path2,
+        index1,
+        index2,
+        path_name,
+        spurious_breakpoints1,
+        spurious_breakpoints2,
+    )?;
+    if let Some(regions) = &options.regions {
+        path_edits = path_edits.restrict(regions.path_intervals(path_name).unwrap_or(&[]));
+    }
+    if let Some(mask) = &options.mask {
+        path_edits = path_edits.mask(mask.path_intervals(path_name).unwrap_or(&[]));
+    }
+    Ok(path_edits)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn compare_paths<F>(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
+    paths: &[String],
+    spurious_breakpoints1: &HashSet<Junction>,
+    spurious_breakpoints2: &HashSet<Junction>,
+    options: &DistanceOptions,
+    mut consume: F,
+) -> io::Result<()>
+where
+    F: FnMut(&str, io::Result<PathComparison>) -> io::Result<()>,
+{
+    /*
+    Compares several paths between two graphs, using several threads
+    Each thread compares one path at a time, with its own readers on the two files
+    Threads do not run further than one path each ahead of the next path to consume, so that a long path
+    does not leave the comparisons of all following paths waiting in memory
+
+    Arguments:
+    - file_path1, file_path2, index1, index2, spurious_breakpoints1, spurious_breakpoints2: see path_edits
+    - paths: the names of the paths to compare
+    - options: the number of paths compared at the same time, the regions where edits are reported and the mask
+    - consume: called with the comparison of each path, in the order of paths whatever the number of threads,
+      or with an error for paths of different lengths, which are not read
+
+    Returns:
+    - the first error returned by consume
+    */
+    let threads: usize = options.threads.max(1);
+    let next_path: AtomicUsize = AtomicUsize::new(0);
+    // Number of paths consumed, threads wait until the path they picked is close enough
+    let consumed_paths: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+    let (sender, receiver) = mpsc::channel::<(usize, io::Result<PathComparison>)>();
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let sender: mpsc::Sender<(usize, io::Result<PathComparison>)> = sender.clone();
+            let next_path: &AtomicUsize = &next_path;
+            let consumed_paths: &(Mutex<usize>, Condvar) = &consumed_paths;
+            scope.spawn(move || loop {
+                let path_index: usize = next_path.fetch_add(1, Ordering::SeqCst);
+                if path_index >= paths.len() {
+                    break;
+                }
+                let path_name: &str = &paths[path_index];
+                let length1: u64 = index1.path_lengths[path_name];
+                let length2: u64 = index2.path_lengths[path_name];
+                if length1 != length2 {
+                    // The path can't be compared, it is reported without waiting for its turn
+                    let error: io::Error = length_error(path_name, length1, length2);
+                    if sender.send((path_index, Err(error))).is_err() {
+                        break;
                     }
-                    node2 = if path_types2[path_name.as_str()] == 'P' {
-                        read_next_p_node(&mut file2, &mut buffer2)
-                    } else {
-                        read_next_w_node(&mut file2, &mut buffer2)
-                    };
-                    breakpoint_b += node_sizes2.get(&node2).unwrap().clone();
+                    continue;
+                }
+                let (lock, condvar) = consumed_paths;
+                let consumed: MutexGuard<usize> = condvar
+                    .wait_while(lock.lock().unwrap(), |consumed| {
+                        path_index >= *consumed + threads
+                    })
+                    .unwrap();
+                if *consumed >= paths.len() {
+                    // An error stopped the comparisons while this thread was waiting
+                    break;
                 }
+                drop(consumed);
+                let comparison: io::Result<PathComparison> = compare_path(
+                    file_path1,
+                    file_path2,
+                    index1,
+                    index2,
+                    path_name,
+                    spurious_breakpoints1,
+                    spurious_breakpoints2,
+                    options,
+                );
+                if sender.send((path_index, comparison)).is_err() {
+                    // Results are not consumed anymore
+                    break;
+                }
+            });
+        }
+        drop(sender);
 
-                // We update the position in the two paths
-                position = min(breakpoint_a, breakpoint_b);
+        // Comparisons can end in any order, we keep them until all previous paths are consumed
+        let mut pending: HashMap<usize, io::Result<PathComparison>> = HashMap::new();
+        let mut consumed: usize = 0;
+        for (path_index, comparison) in receiver.iter() {
+            pending.insert(path_index, comparison);
+            while let Some(comparison) = pending.remove(&consumed) {
+                let result: io::Result<()> = consume(&paths[consumed], comparison);
+                consumed += 1;
+                if result.is_err() {
+                    // Stop every thread as soon as their current path is compared
+                    next_path.store(paths.len(), Ordering::SeqCst);
+                    consumed = paths.len();
+                }
+                // Threads waiting for this path to be consumed can go on
+                let (lock, condvar) = &consumed_paths;
+                *lock.lock().unwrap() = consumed;
+                condvar.notify_all();
+                result?;
             }
         }
+        Ok(())
+    })
+}
+
+fn length_error(path_name: &str, length1: u64, length2: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "the two paths representing {} have different lengths: {} and {}",
+            path_name, length1, length2
+        ),
+    )
+}
+
+#[derive(Clone, Debug)]
+pub struct DistanceOptions {
+    // Number of paths compared at the same time
+    pub threads: usize,
+    // Format of the report written to standard output
+    pub format: OutputFormat,
+    // Intervals of paths where edits are reported, all paths entirely if None
+    pub regions: Option<Regions>,
+    // Intervals of paths where edits are dropped
+    pub mask: Option<Regions>,
+    // Size of windows to write edit densities as bedGraph instead of the report
+    pub window: Option<u64>,
+    // File where spurious breakpoints are listed, not written if None
+    pub spurious_report: Option<String>,
+}
+
+impl Default for DistanceOptions {
+    fn default() -> DistanceOptions {
+        DistanceOptions {
+            threads: 1,
+            format: OutputFormat::Tsv,
+            regions: None,
+            mask: None,
+            window: None,
+            spurious_report: None,
+        }
     }
-    println!(
-        "# Distance: {} (E={}, S={}, M={}, SP={}).",
-        splits_count + merges_count,
-        equivalences_count,
-        splits_count,
-        merges_count,
-        spurious_count
-    );
-    Ok(())
 }
 
-fn read_next_p_node(file: &mut BufReader<File>, buffer: &mut [u8; 1]) -> String {
-    /*
-     * Read the next node in the file, until a comma is found
-     * file: the file to read
-     * buffer: a buffer to read the file
-     */
-    let mut node = String::new();
-    while file.read(buffer).unwrap() > 0 {
-        if buffer[0] == b'+' || buffer[0] == b'-' {
-            break;
+struct DistanceOutput<'a> {
+    file_path1: &'a str,
+    file_path2: &'a str,
+    writer: ReportWriter<io::BufWriter<io::Stdout>>,
+    // Densities of edits, written instead of the report if a window size is given
+    density: Option<EditDensity>,
+    // Where spurious breakpoints are listed, if a spurious report is given
+    spurious_writer: Option<io::BufWriter<File>>,
+    report: DistanceReport,
+}
+
+impl DistanceOutput<'_> {
+    fn skipped_path(&mut self, path_name: &str, length1: u64, length2: u64) -> io::Result<()> {
+        /*
+        Reports a path that can't be compared, as it has different lengths in the two graphs
+        */
+        // Densities have no room for skipped paths, they are only given back in the report
+        if self.density.is_none() {
+            self.writer.skipped_path(path_name, length1, length2)?;
+        }
+        self.report
+            .skipped_paths
+            .push((path_name.to_string(), length1, length2));
+        Ok(())
+    }
+
+    fn start_path(&mut self, path_name: &str, length: u64) {
+        if let Some(density) = self.density.as_mut() {
+            density.start_path(path_name, length);
         }
-        if buffer[0] != b',' {
-            node.push(buffer[0] as char);
+    }
+
+    fn edit(&mut self, edit: &Edit) -> io::Result<()> {
+        /*
+        Writes an edit of the current path, or counts it in its window
+        */
+        match self.density.as_mut() {
+            Some(density) => density.add_edit(edit),
+            None => self.writer.edit(edit)?,
         }
+        Ok(())
+    }
+
+    fn end_path(
+        &mut self,
+        summary: PathSummary,
+        spurious: &[SpuriousBreakpoint],
+    ) -> io::Result<()> {
+        /*
+        Records the counts of the current path, and lists its spurious breakpoints
+        */
+        if let Some(spurious_writer) = self.spurious_writer.as_mut() {
+            for breakpoint in spurious.iter() {
+                let graph: &str = match breakpoint.op {
+                    Operation::Split => self.file_path1,
+                    Operation::Merge => self.file_path2,
+                };
+                let (node, strand, next, next_strand) = &breakpoint.junction;
+                writeln!(
+                    spurious_writer,
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    graph,
+                    breakpoint.path,
+                    breakpoint.position,
+                    breakpoint.op.as_char(),
+                    node,
+                    strand,
+                    next,
+                    next_strand
+                )?;
+            }
+        }
+        self.report.counts.add(&summary.counts);
+        self.report.paths.push(summary);
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<DistanceReport> {
+        /*
+        Writes the summary or the densities, once every path is compared
+        */
+        match self.density.as_ref() {
+            Some(density) => density.write(&mut io::BufWriter::new(io::stdout()))?,
+            None => self.writer.summary(&self.report)?,
+        }
+        if let Some(spurious_writer) = self.spurious_writer.as_mut() {
+            spurious_writer.flush()?;
+        }
+        Ok(self.report)
     }
-    node
 }
 
-fn read_next_w_node(file: &mut BufReader<File>, buffer: &mut [u8; 1]) -> String {
+pub fn distance(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
+    spurious_breakpoints1: HashSet<Junction>,
+    spurious_breakpoints2: HashSet<Junction>,
+    options: &DistanceOptions,
+) -> io::Result<DistanceReport> {
     /*
-     * Read the next node in the file, until a > or < is found
-     * file: the file to read
-     * buffer: a buffer to read the file
-     */
-    let mut node = String::new();
-    while file.read(buffer).unwrap() > 0 {
-        if buffer[0] == b'>' || buffer[0] == b'<' || buffer[0] == b'\t' {
-            break;
-        } else {
-            node.push(buffer[0] as char);
+    Given two GFA files and their indexes (node sizes, path positions, lengths and types), this function computes the distance between the two graphs.
+
+    Arguments:
+    - file_path1: the path to the first GFA file
+    - file_path2: the path to the second GFA file
+    - index1: the index of the first GFA file
+    - index2: the index of the second GFA file
+    - spurious_breakpoints1: spurious junctions of the first GFA file
+    - spurious_breakpoints2: spurious junctions of the second GFA file
+    - options: the number of threads, the output format, the regions where edits are reported, the mask, the window size for densities
+      and the file where spurious breakpoints are listed
+
+    Ouptut:
+    - Writes to standard output the operations (merges and splits) needed to transform the first graph into the second graph, or their densities along paths
+    - Writes to the spurious report, if given, the spurious breakpoints that were not counted as operations
+    - Returns the report of the comparison
+    */
+    let intersection: Vec<String> = path_intersection(index1, index2);
+    let mut writer: ReportWriter<io::BufWriter<io::Stdout>> =
+        ReportWriter::new(io::BufWriter::new(io::stdout()), options.format);
+
+    // If regions are given, only paths holding a region are compared
+    let compared_paths: Vec<String> = match &options.regions {
+        Some(regions) => intersection
+            .iter()
+            .filter(|path_name| regions.path_intervals(path_name).is_some())
+            .cloned()
+            .collect(),
+        None => intersection.clone(),
+    };
+
+    // If a window size is given, edits are written as densities instead of the report
+    let density: Option<EditDensity> = options.window.map(EditDensity::new);
+    if density.is_none() {
+        writer.header(&intersection, index1, index2)?;
+    }
+    // If a spurious report is given, spurious breakpoints are listed with the graph they belong to
+    let spurious_writer: Option<io::BufWriter<File>> = match &options.spurious_report {
+        Some(report_path) => {
+            let mut spurious_writer: io::BufWriter<File> =
+                io::BufWriter::new(File::create(report_path)?);
+            writeln!(
+                spurious_writer,
+                "# Graph\tPath name\tPosition\tOperation\tNode\tStrand\tNext node\tNext strand"
+            )?;
+            Some(spurious_writer)
+        }
+        None => None,
+    };
+    let mut output: DistanceOutput = DistanceOutput {
+        file_path1,
+        file_path2,
+        writer,
+        density,
+        spurious_writer,
+        report: DistanceReport {
+            intersection,
+            ..DistanceReport::default()
+        },
+    };
+
+    if options.threads <= 1 {
+        // Edits are written as soon as they are found
+        for path_name in compared_paths.iter() {
+            let length1: u64 = index1.path_lengths[path_name];
+            let length2: u64 = index2.path_lengths[path_name];
+            if length1 != length2 {
+                // The two paths have different lengths, we cannot compare them
+                output.skipped_path(path_name, length1, length2)?;
+                continue;
+            }
+            let mut path_edits: PathEdits = selected_path_edits(
+                file_path1,
+                file_path2,
+                index1,
+                index2,
+                path_name,
+                &spurious_breakpoints1,
+                &spurious_breakpoints2,
+                options,
+            )?;
+            output.start_path(path_name, length1);
+            for edit in path_edits.by_ref() {
+                output.edit(&edit?)?;
+            }
+            output.end_path(path_edits.summary(), &path_edits.spurious)?;
         }
+    } else {
+        // Edits of each path are collected by the threads, and written in the order of paths
+        compare_paths(
+            file_path1,
+            file_path2,
+            index1,
+            index2,
+            &compared_paths,
+            &spurious_breakpoints1,
+            &spurious_breakpoints2,
+            options,
+            |path_name, comparison| {
+                let length1: u64 = index1.path_lengths[path_name];
+                let length2: u64 = index2.path_lengths[path_name];
+                if length1 != length2 {
+                    // The two paths have different lengths, we cannot compare them
+                    return output.skipped_path(path_name, length1, length2);
+                }
+                let comparison: PathComparison = comparison?;
+                output.start_path(path_name, length1);
+                for edit in comparison.edits.iter() {
+                    output.edit(edit)?;
+                }
+                output.end_path(comparison.summary, &comparison.spurious)
+            },
+        )?;
+    }
+    output.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_gfa_file::parse_gfa;
+
+    #[test]
+    fn test_path_edits() {
+        let path_a = std::env::temp_dir().join(format!("{}_edits_a.gfa", std::process::id()));
+        let path_b = std::env::temp_dir().join(format!("{}_edits_b.gfa", std::process::id()));
+        let (path_a, path_b): (&str, &str) = (path_a.to_str().unwrap(), path_b.to_str().unwrap());
+        // A cuts the path at 2 and 4, B cuts it at 3 and 4
+        std::fs::write(path_a, "S\t1\tAC\nS\t2\tGT\nS\t3\tA\nP\tx\t1+,2-,3+\t*\n").unwrap();
+        std::fs::write(path_b, "S\t4\tACG\nS\t5\tT\nS\t6\tA\nP\tx\t4+,5+,6+\t*\n").unwrap();
+        let index_a: GfaIndex = parse_gfa(path_a).unwrap();
+        let index_b: GfaIndex = parse_gfa(path_b).unwrap();
+        let none: HashSet<Junction> = HashSet::new();
+
+        let mut edits: EditIterator = edits(path_a, path_b, &index_a, &index_b, &none, &none);
+        let found: Vec<Edit> = edits.by_ref().collect::<io::Result<Vec<Edit>>>().unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].position, found[0].op), (2, Operation::Split));
+        assert_eq!(
+            (found[0].node_a.as_str(), found[0].node_b.as_str()),
+            ("1", "4")
+        );
+        assert_eq!((found[1].position, found[1].op), (3, Operation::Merge));
+        // Node 2 is reversed, so position 3 is at the end of its forward strand
+        assert_eq!((found[1].strand_a, found[1].offset_a), ('-', 1));
+        assert_eq!(edits.report().distance(), 2);
+        assert_eq!(edits.report().counts.equivalences, 2);
+        // Breakpoint 4 is shared, 2 is only in A and 3 only in B
+        let summary: &PathSummary = &edits.report().paths[0];
+        assert_eq!(summary.counts.shared, 1);
+        assert_eq!((summary.counts.nodes_a, summary.counts.nodes_b), (3, 3));
+        assert!((summary.jaccard() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((summary.precision() - 0.5).abs() < 1e-9);
+        assert!((summary.f1() - 0.5).abs() < 1e-9);
+
+        // Only the merge at position 3 falls inside the region
+        let restricted: Vec<Edit> =
+            path_edits(path_a, path_b, &index_a, &index_b, "x", &none, &none)
+                .unwrap()
+                .restrict(&[(3, 5)])
+                .collect::<io::Result<Vec<Edit>>>()
+                .unwrap();
+        assert_eq!(restricted.len(), 1);
+        assert_eq!(restricted[0].op, Operation::Merge);
+
+        // The split at position 2 is masked, and counted as such
+        let mut masked: PathEdits =
+            path_edits(path_a, path_b, &index_a, &index_b, "x", &none, &none)
+                .unwrap()
+                .mask(&[(0, 3)]);
+        let remaining: Vec<Edit> = masked.by_ref().collect::<io::Result<Vec<Edit>>>().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].position, 3);
+        assert_eq!((masked.counts.splits, masked.counts.masked), (0, 1));
+        assert_eq!(masked.summary().length, 2);
+
+        // The junction of nodes 1 and 2 in A is spurious, the split at position 2 is not reported
+        let spurious: HashSet<Junction> =
+            HashSet::from([("1".to_string(), '+', "2".to_string(), '-')]);
+        let mut filtered: PathEdits =
+            path_edits(path_a, path_b, &index_a, &index_b, "x", &spurious, &none).unwrap();
+        let remaining: Vec<Edit> = filtered
+            .by_ref()
+            .collect::<io::Result<Vec<Edit>>>()
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!((filtered.counts.splits, filtered.counts.spurious), (0, 1));
+        assert_eq!(
+            filtered.spurious,
+            vec![SpuriousBreakpoint {
+                path: "x".to_string(),
+                position: 2,
+                op: Operation::Split,
+                junction: ("1".to_string(), '+', "2".to_string(), '-'),
+            }]
+        );
+
+        std::fs::remove_file(path_a).unwrap();
+        std::fs::remove_file(path_b).unwrap();
+    }
+
+    #[test]
+    fn test_similarity_metrics() {
+        let summary = |shared: u64, splits: u64, merges: u64| PathSummary {
+            path_name: "x".to_string(),
+            length: 100,
+            counts: EditCounts {
+                shared,
+                splits,
+                merges,
+                nodes_a: shared + splits + 1,
+                nodes_b: shared + merges + 1,
+                ..EditCounts::default()
+            },
+        };
+        let close = |value: f64, expected: f64| (value - expected).abs() < 1e-9;
+
+        // A has 4 breakpoints, B has 5, 3 of them are shared
+        let metrics: PathSummary = summary(3, 1, 2);
+        assert!(close(metrics.distance_per_base(), 0.03));
+        assert!(close(metrics.distance_per_node(), 3.0 / 11.0));
+        assert!(close(metrics.jaccard(), 0.5));
+        assert!(close(metrics.distance_per_breakpoint(), 0.5));
+        assert!(close(metrics.precision(), 0.75));
+        assert!(close(metrics.recall(), 0.6));
+        assert!(close(metrics.f1(), 2.0 / 3.0));
+
+        // Without breakpoints in either graph, both graphs agree
+        let metrics: PathSummary = summary(0, 0, 0);
+        assert_eq!(
+            (metrics.jaccard(), metrics.distance_per_breakpoint()),
+            (1.0, 0.0)
+        );
+        assert_eq!(
+            (metrics.precision(), metrics.recall(), metrics.f1()),
+            (1.0, 1.0, 1.0)
+        );
+        // Without shared breakpoints, they disagree everywhere
+        let metrics: PathSummary = summary(0, 1, 2);
+        assert_eq!(
+            (metrics.jaccard(), metrics.distance_per_breakpoint()),
+            (0.0, 1.0)
+        );
+        assert_eq!(
+            (metrics.precision(), metrics.recall(), metrics.f1()),
+            (0.0, 0.0, 0.0)
+        );
+        // Only B has breakpoints
+        let metrics: PathSummary = summary(0, 0, 2);
+        assert_eq!((metrics.precision(), metrics.recall()), (1.0, 0.0));
+        assert!(close(metrics.f1(), 0.0));
+    }
+
+    #[test]
+    fn test_mask_boundaries() {
+        let path_a = std::env::temp_dir().join(format!("{}_mask_a.gfa", std::process::id()));
+        let path_b = std::env::temp_dir().join(format!("{}_mask_b.gfa", std::process::id()));
+        let (path_a, path_b): (&str, &str) = (path_a.to_str().unwrap(), path_b.to_str().unwrap());
+        // A splits at 2, B merges at 3, both cut the path at 4
+        std::fs::write(path_a, "S\t1\tAC\nS\t2\tGT\nS\t3\tA\nP\tx\t1+,2+,3+\t*\n").unwrap();
+        std::fs::write(path_b, "S\t4\tACG\nS\t5\tT\nS\t6\tA\nP\tx\t4+,5+,6+\t*\n").unwrap();
+        let index_a: GfaIndex = parse_gfa(path_a).unwrap();
+        let index_b: GfaIndex = parse_gfa(path_b).unwrap();
+        let none: HashSet<Junction> = HashSet::new();
+
+        // Masked intervals are half-open, as in BED: their start is masked, their end is not
+        for (mask, positions, masked) in [
+            ((2, 3), vec![3], 1),
+            ((0, 2), vec![2, 3], 0),
+            ((3, 4), vec![2], 1),
+            ((2, 4), vec![], 2),
+        ] {
+            let mut edits: PathEdits =
+                path_edits(path_a, path_b, &index_a, &index_b, "x", &none, &none)
+                    .unwrap()
+                    .mask(&[mask]);
+            let found: Vec<u64> = edits.by_ref().map(|edit| edit.unwrap().position).collect();
+            assert_eq!(found, positions);
+            assert_eq!(edits.counts.masked, masked);
+            assert_eq!(edits.counts.distance(), 2 - masked);
+            assert_eq!(edits.summary().length, 5 - (mask.1 - mask.0));
+        }
+
+        std::fs::remove_file(path_a).unwrap();
+        std::fs::remove_file(path_b).unwrap();
+    }
+
+    #[test]
+    fn test_compare_paths_order() {
+        let path_a = std::env::temp_dir().join(format!("{}_threads_a.gfa", std::process::id()));
+        let path_b = std::env::temp_dir().join(format!("{}_threads_b.gfa", std::process::id()));
+        let (path_a, path_b): (&str, &str) = (path_a.to_str().unwrap(), path_b.to_str().unwrap());
+        std::fs::write(
+            path_a,
+            "S\t1\tAC\nS\t2\tGT\nP\tx\t1+,2+\t*\nP\ty\t2+,1+\t*\nP\tz\t1+\t*\nP\tw\t1+\t*\n",
+        )
+        .unwrap();
+        std::fs::write(
+            path_b,
+            "S\t3\tACGT\nS\t4\tA\nS\t5\tC\nP\tx\t3+\t*\nP\ty\t4+,5+,4+,5+\t*\nP\tz\t4+,5+\t*\nP\tw\t4+\t*\n",
+        )
+        .unwrap();
+        let index_a: GfaIndex = parse_gfa(path_a).unwrap();
+        let index_b: GfaIndex = parse_gfa(path_b).unwrap();
+        let paths: Vec<String> = vec!["y".to_string(), "z".to_string(), "x".to_string()];
+
+        let mut results: Vec<Vec<(String, u64, EditCounts)>> = Vec::new();
+        for threads in [1, 2, 3] {
+            let mut found: Vec<(String, u64, EditCounts)> = Vec::new();
+            compare_paths(
+                path_a,
+                path_b,
+                &index_a,
+                &index_b,
+                &paths,
+                &HashSet::new(),
+                &HashSet::new(),
+                &DistanceOptions {
+                    threads,
+                    ..DistanceOptions::default()
+                },
+                |path_name, comparison| {
+                    let comparison: PathComparison = comparison?;
+                    found.push((
+                        path_name.to_string(),
+                        comparison.edits.len() as u64,
+                        comparison.summary.counts,
+                    ));
+                    Ok(())
+                },
+            )
+            .unwrap();
+            results.push(found);
+        }
+        // With two threads, the third path waits for the first one to be consumed
+        assert_eq!(results[0], results[1]);
+        assert_eq!(results[0], results[2]);
+        let names: Vec<&str> = results[0]
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .collect();
+        assert_eq!(names, vec!["y", "z", "x"]);
+        assert_eq!(results[0][2].1, 1);
+
+        // w has different lengths and is given as an error, which stops the comparisons
+        let paths: Vec<String> = vec!["w".to_string(), "y".to_string(), "z".to_string()];
+        for threads in [1, 2, 3] {
+            let mut consumed: Vec<String> = Vec::new();
+            let result: io::Result<()> = compare_paths(
+                path_a,
+                path_b,
+                &index_a,
+                &index_b,
+                &paths,
+                &HashSet::new(),
+                &HashSet::new(),
+                &DistanceOptions {
+                    threads,
+                    ..DistanceOptions::default()
+                },
+                |path_name, comparison| {
+                    consumed.push(path_name.to_string());
+                    comparison.map(|_| ())
+                },
+            );
+            assert!(result.is_err());
+            assert_eq!(consumed, vec!["w".to_string()]);
+        }
+
+        std::fs::remove_file(path_a).unwrap();
+        std::fs::remove_file(path_b).unwrap();
+    }
+
+    #[test]
+    fn test_spurious_report() {
+        let path_a = std::env::temp_dir().join(format!("{}_report_a.gfa", std::process::id()));
+        let path_b = std::env::temp_dir().join(format!("{}_report_b.gfa", std::process::id()));
+        let report_path = std::env::temp_dir().join(format!("{}_spurious.tsv", std::process::id()));
+        let (path_a, path_b): (&str, &str) = (path_a.to_str().unwrap(), path_b.to_str().unwrap());
+        // A cuts the path at 2, on a spurious junction, B does not cut it
+        std::fs::write(path_a, "S\t1\tAC\nS\t2\tGT\nP\tx\t1+,2-\t*\n").unwrap();
+        std::fs::write(path_b, "S\t3\tACAC\nP\tx\t3+\t*\n").unwrap();
+        let index_a: GfaIndex = parse_gfa(path_a).unwrap();
+        let index_b: GfaIndex = parse_gfa(path_b).unwrap();
+        let spurious_a: HashSet<Junction> =
+            HashSet::from([("1".to_string(), '+', "2".to_string(), '-')]);
+        let spurious_b: HashSet<Junction> = HashSet::new();
+        let mut edits: PathEdits = path_edits(
+            path_a,
+            path_b,
+            &index_a,
+            &index_b,
+            "x",
+            &spurious_a,
+            &spurious_b,
+        )
+        .unwrap();
+        assert_eq!(edits.by_ref().count(), 0);
+
+        let mut output: DistanceOutput = DistanceOutput {
+            file_path1: path_a,
+            file_path2: path_b,
+            writer: ReportWriter::new(io::BufWriter::new(io::stdout()), OutputFormat::default()),
+            density: None,
+            spurious_writer: Some(io::BufWriter::new(File::create(&report_path).unwrap())),
+            report: DistanceReport::default(),
+        };
+        output.end_path(edits.summary(), &edits.spurious).unwrap();
+        output.spurious_writer.take().unwrap().flush().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&report_path).unwrap(),
+            format!("{}\tx\t2\tS\t1\t+\t2\t-\n", path_a)
+        );
+        assert_eq!(output.report.counts.spurious, 1);
+
+        std::fs::remove_file(path_a).unwrap();
+        std::fs::remove_file(path_b).unwrap();
+        std::fs::remove_file(report_path).unwrap();
     }
-    node
 }
@@ -0,0 +1,428 @@
+use crate::compute_distance::{compare_paths, DistanceOptions, PathComparison};
+use crate::index_gfa_file::GfaIndex;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::str::FromStr;
+// Several graphs built from the same genomes can be compared all against all: each graph is indexed once,
+// and the distance is computed for every pair of graphs on the paths found in all graphs, so that distances can be compared.
+// A path with different lengths in two graphs can't be compared between them, so it is left out of every pair:
+// all distances are then sums over the same set of paths.
+// The distance is symmetric (splits of one graph are merges of the other), so each pair is compared once.
+// Graphs are then clustered in a tree, with UPGMA (rooted, assumes a constant rate) or neighbour-joining (unrooted),
+// written in Newick format with distances as branch lengths.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TreeMethod {
+    #[default]
+    Upgma,
+    NeighbourJoining,
+}
+
+impl FromStr for TreeMethod {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<TreeMethod, String> {
+        match name {
+            "upgma" => Ok(TreeMethod::Upgma),
+            "nj" => Ok(TreeMethod::NeighbourJoining),
+            _ => Err(format!(
+                "unknown tree method {} (expected upgma or nj)",
+                name
+            )),
+        }
+    }
+}
+
+pub fn common_paths(indexes: &[GfaIndex]) -> (Vec<String>, Vec<String>) {
+    /*
+    Finds the paths that can be compared between every pair of graphs
+
+    Arguments:
+    - indexes: the indexes of the graphs
+
+    Returns:
+    - the names of the paths found in all graphs with the same length, sorted by name
+    - the names of the paths found in all graphs but with different lengths, sorted by name
+    */
+    let mut paths: Vec<String> = match indexes.first() {
+        Some(index) => index
+            .path_positions
+            .keys()
+            .filter(|&k| indexes.iter().all(|i| i.path_positions.contains_key(k)))
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    paths.sort();
+    paths.into_iter().partition(|path_name| {
+        indexes
+            .iter()
+            .all(|index| index.path_lengths[path_name] == indexes[0].path_lengths[path_name])
+    })
+}
+
+pub fn graph_names(file_paths: &[String]) -> Vec<String> {
+    /*
+    Names graphs after their file names, without directories and extensions (.gfa, .gz)
+    If two files have the same name, graphs are named after their whole file paths
+    */
+    let names: Vec<String> = file_paths
+        .iter()
+        .map(|file_path| {
+            let file_name: &str = file_path.rsplit('/').next().unwrap_or(file_path);
+            let file_name: &str = file_name.strip_suffix(".gz").unwrap_or(file_name);
+            file_name
+                .strip_suffix(".gfa")
+                .unwrap_or(file_name)
+                .to_string()
+        })
+        .collect();
+    let mut sorted: Vec<&String> = names.iter().collect();
+    sorted.sort();
+    sorted.dedup();
+    if sorted.len() < names.len() {
+        return file_paths.to_vec();
+    }
+    names
+}
+
+pub fn pair_distance(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
+    paths: &[String],
+    options: &DistanceOptions,
+) -> io::Result<u64> {
+    /*
+    Computes the distance between two graphs on the given paths, without writing the edits
+    Returns an error if a path has different lengths in the two graphs, as the distance would miss it
+    */
+    let mut distance: u64 = 0;
+    compare_paths(
+        file_path1,
+        file_path2,
+        index1,
+        index2,
+        paths,
+        &HashSet::new(),
+        &HashSet::new(),
+        options,
+        |path_name, comparison| {
+            let length1: u64 = index1.path_lengths[path_name];
+            let length2: u64 = index2.path_lengths[path_name];
+            if length1 != length2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "the two paths representing {} in {} and {} have different lengths: {} and {}",
+                        path_name, file_path1, file_path2, length1, length2
+                    ),
+                ));
+            }
+            let comparison: PathComparison = comparison?;
+            distance += comparison.summary.counts.distance();
+            Ok(())
+        },
+    )?;
+    Ok(distance)
+}
+
+pub fn distance_matrix(
+    file_paths: &[String],
+    indexes: &[GfaIndex],
+    paths: &[String],
+    options: &DistanceOptions,
+) -> io::Result<Vec<Vec<u64>>> {
+    /*
+    Computes the distances between all pairs of graphs
+
+    Arguments:
+    - file_paths: the paths to the GFA files
+    - indexes: the indexes of the GFA files, in the same order
+    - paths: the names of the paths to compare, with the same length in all graphs (see common_paths)
+    - options: the number of paths compared at the same time, the regions where edits are counted and the mask
+
+    Returns:
+    - the symmetric matrix of distances, with zeros on the diagonal
+    */
+    let mut matrix: Vec<Vec<u64>> = vec![vec![0; file_paths.len()]; file_paths.len()];
+    for i in 0..file_paths.len() {
+        for j in (i + 1)..file_paths.len() {
+            let distance: u64 = pair_distance(
+                &file_paths[i],
+                &file_paths[j],
+                &indexes[i],
+                &indexes[j],
+                paths,
+                options,
+            )?;
+            matrix[i][j] = distance;
+            matrix[j][i] = distance;
+        }
+    }
+    Ok(matrix)
+}
+
+fn newick_name(name: &str) -> String {
+    /*
+    Quotes a name holding characters with a meaning in Newick format
+    */
+    if name.contains(|c: char| "()[]':;, \t".contains(c)) {
+        format!("'{}'", name.replace('\'', "''"))
+    } else {
+        name.to_string()
+    }
+}
+
+pub fn upgma(names: &[String], matrix: &[Vec<u64>]) -> String {
+    /*
+    Builds a rooted tree by joining the two closest clusters until one is left,
+    the distance between two clusters being the mean distance between their graphs
+
+    Arguments:
+    - names: the names of the graphs
+    - matrix: the distances between graphs
+
+    Returns:
+    - the tree in Newick format
+    */
+    // Subtrees in Newick format, their number of graphs and their heights
+    let mut clusters: Vec<(String, usize, f64)> = names
+        .iter()
+        .map(|name| (newick_name(name), 1, 0.0))
+        .collect();
+    let mut distances: Vec<Vec<f64>> = matrix
+        .iter()
+        .map(|row| row.iter().map(|&d| d as f64).collect())
+        .collect();
+    while clusters.len() > 1 {
+        // We find the closest pair of clusters, the first one in case of ties
+        let (mut closest_i, mut closest_j): (usize, usize) = (0, 1);
+        for i in 0..clusters.len() {
+            for j in (i + 1)..clusters.len() {
+                if distances[i][j] < distances[closest_i][closest_j] {
+                    (closest_i, closest_j) = (i, j);
+                }
+            }
+        }
+        let height: f64 = distances[closest_i][closest_j] / 2.0;
+        let (tree_j, size_j, height_j) = clusters.remove(closest_j);
+        let (tree_i, size_i, height_i) = &clusters[closest_i];
+        let joined: (String, usize, f64) = (
+            format!(
+                "({}:{},{}:{})",
+                tree_i,
+                height - height_i,
+                tree_j,
+                height - height_j
+            ),
+            size_i + size_j,
+            height,
+        );
+        // The new cluster replaces the first one, its distances are averaged over graphs
+        let row_j: Vec<f64> = distances.remove(closest_j);
+        for row in distances.iter_mut() {
+            row.remove(closest_j);
+        }
+        for k in 0..clusters.len() {
+            let distance: f64 = (distances[closest_i][k] * *size_i as f64
+                + row_j[if k < closest_j { k } else { k + 1 }] * size_j as f64)
+                / (size_i + size_j) as f64;
+            distances[closest_i][k] = distance;
+            distances[k][closest_i] = distance;
+        }
+        distances[closest_i][closest_i] = 0.0;
+        clusters[closest_i] = joined;
+    }
+    match clusters.pop() {
+        Some((tree, _, _)) => format!("{};", tree),
+        None => ";".to_string(),
+    }
+}
+
+pub fn neighbour_joining(names: &[String], matrix: &[Vec<u64>]) -> String {
+    /*
+    Builds an unrooted tree by joining the pair of clusters minimising the total branch length,
+    until three clusters are left, joined at the root
+    Negative branch lengths, found on non-additive distances, are set to zero
+
+    Arguments:
+    - names: the names of the graphs
+    - matrix: the distances between graphs
+
+    Returns:
+    - the tree in Newick format
+    */
+    let mut clusters: Vec<String> = names.iter().map(|name| newick_name(name)).collect();
+    let mut distances: Vec<Vec<f64>> = matrix
+        .iter()
+        .map(|row| row.iter().map(|&d| d as f64).collect())
+        .collect();
+    while clusters.len() > 3 {
+        let n: usize = clusters.len();
+        let totals: Vec<f64> = distances.iter().map(|row| row.iter().sum()).collect();
+        let (mut closest_i, mut closest_j): (usize, usize) = (0, 1);
+        let mut minimum: f64 = f64::INFINITY;
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let q: f64 = (n - 2) as f64 * distances[i][j] - totals[i] - totals[j];
+                if q < minimum {
+                    minimum = q;
+                    (closest_i, closest_j) = (i, j);
+                }
+            }
+        }
+        let distance: f64 = distances[closest_i][closest_j];
+        let branch_i: f64 =
+            distance / 2.0 + (totals[closest_i] - totals[closest_j]) / (2.0 * (n - 2) as f64);
+        let branch_j: f64 = distance - branch_i;
+        let tree_j: String = clusters.remove(closest_j);
+        clusters[closest_i] = format!(
+            "({}:{},{}:{})",
+            clusters[closest_i],
+            branch_i.max(0.0),
+            tree_j,
+            branch_j.max(0.0)
+        );
+        // Distances to the new cluster are measured from the node joining the pair
+        let row_j: Vec<f64> = distances.remove(closest_j);
+        for row in distances.iter_mut() {
+            row.remove(closest_j);
+        }
+        for k in 0..clusters.len() {
+            let joined: f64 =
+                (distances[closest_i][k] + row_j[if k < closest_j { k } else { k + 1 }] - distance)
+                    / 2.0;
+            distances[closest_i][k] = joined;
+            distances[k][closest_i] = joined;
+        }
+        distances[closest_i][closest_i] = 0.0;
+    }
+    match clusters.len() {
+        0 => ";".to_string(),
+        1 => format!("{};", clusters[0]),
+        2 => format!(
+            "({}:{},{}:{});",
+            clusters[0],
+            distances[0][1] / 2.0,
+            clusters[1],
+            distances[0][1] / 2.0
+        ),
+        _ => {
+            let (d01, d02, d12): (f64, f64, f64) =
+                (distances[0][1], distances[0][2], distances[1][2]);
+            format!(
+                "({}:{},{}:{},{}:{});",
+                clusters[0],
+                ((d01 + d02 - d12) / 2.0).max(0.0),
+                clusters[1],
+                ((d01 + d12 - d02) / 2.0).max(0.0),
+                clusters[2],
+                ((d02 + d12 - d01) / 2.0).max(0.0)
+            )
+        }
+    }
+}
+
+pub fn write_matrix<W: Write>(
+    writer: &mut W,
+    names: &[String],
+    paths: &[String],
+    excluded: &[String],
+    matrix: &[Vec<u64>],
+    tree: &str,
+) -> io::Result<()> {
+    /*
+    Writes the paths compared, the paths left out, the distance matrix as TSV (one row and one column per graph) and the tree
+    */
+    writeln!(writer, "# Intersection of paths: {:?}", paths)?;
+    writeln!(writer, "# Excluded paths: {:?}", excluded)?;
+    writeln!(writer, "# Graph\t{}", names.join("\t"))?;
+    for (name, row) in names.iter().zip(matrix.iter()) {
+        let row: Vec<String> = row.iter().map(|d| d.to_string()).collect();
+        writeln!(writer, "{}\t{}", name, row.join("\t"))?;
+    }
+    writeln!(writer, "# Newick\t{}", tree)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn names(count: usize) -> Vec<String> {
+        ["A", "B", "C", "D"][..count]
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_upgma() {
+        let matrix: Vec<Vec<u64>> = vec![
+            vec![0, 2, 6, 6],
+            vec![2, 0, 6, 6],
+            vec![6, 6, 0, 4],
+            vec![6, 6, 4, 0],
+        ];
+        assert_eq!(upgma(&names(4), &matrix), "((A:1,B:1):2,(C:2,D:2):1);");
+        assert_eq!(upgma(&names(1), &[vec![0]]), "A;");
+    }
+
+    #[test]
+    fn test_neighbour_joining() {
+        // Additive distances of the tree ((A:1,B:2):1,C:3,D:4)
+        let matrix: Vec<Vec<u64>> = vec![
+            vec![0, 3, 5, 6],
+            vec![3, 0, 6, 7],
+            vec![5, 6, 0, 7],
+            vec![6, 7, 7, 0],
+        ];
+        assert_eq!(
+            neighbour_joining(&names(4), &matrix),
+            "((A:1,B:2):1,C:3,D:4);"
+        );
+        assert_eq!(
+            neighbour_joining(&names(2), &[vec![0, 4], vec![4, 0]]),
+            "(A:2,B:2);"
+        );
+    }
+
+    #[test]
+    fn test_common_paths() {
+        let index = |paths: &[(&str, u64)]| GfaIndex {
+            seq_lengths: HashMap::new(),
+            path_positions: paths
+                .iter()
+                .map(|(name, _)| (name.to_string(), vec![0]))
+                .collect(),
+            path_lengths: paths
+                .iter()
+                .map(|(name, length)| (name.to_string(), *length))
+                .collect(),
+            path_types: HashMap::new(),
+            path_gaps: HashMap::new(),
+        };
+        // z is missing from the last graph, y is shorter in the second graph
+        let indexes: Vec<GfaIndex> = vec![
+            index(&[("x", 5), ("y", 4), ("z", 2)]),
+            index(&[("x", 5), ("y", 3), ("z", 2)]),
+            index(&[("x", 5), ("y", 4)]),
+        ];
+        assert_eq!(
+            common_paths(&indexes),
+            (vec!["x".to_string()], vec!["y".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_graph_names() {
+        let file_paths: Vec<String> = vec!["data/pggb.gfa.gz".to_string(), "mc.gfa".to_string()];
+        assert_eq!(graph_names(&file_paths), vec!["pggb", "mc"]);
+        let file_paths: Vec<String> = vec!["a/g.gfa".to_string(), "b/g.gfa".to_string()];
+        assert_eq!(graph_names(&file_paths), file_paths);
+        assert_eq!(newick_name("a b"), "'a b'");
+    }
+}
@@ -0,0 +1,145 @@
+use crate::compute_distance::{Edit, Operation};
+use std::io::{self, Write};
+// Edits can be summarised as densities along paths: each path is cut in windows of fixed size,
+// and splits, merges and edits (splits and merges) are counted in each window.
+// Densities are written as bedGraph, with one track for each count, so they can be loaded in genome browsers.
+
+pub struct EditDensity {
+    // Size of windows, in positions on the path
+    window: u64,
+    // Densities of the paths, in the order they were added
+    paths: Vec<PathDensity>,
+}
+
+struct PathDensity {
+    path_name: String,
+    length: u64,
+    // Counts of splits and merges in each window of the path
+    windows: Vec<(u64, u64)>,
+}
+
+impl EditDensity {
+    pub fn new(window: u64) -> EditDensity {
+        EditDensity {
+            window: window.max(1),
+            paths: Vec::new(),
+        }
+    }
+
+    pub fn add_path(&mut self, path_name: &str, length: u64, edits: &[Edit]) {
+        /*
+        Counts the edits of a path in each window
+
+        Arguments:
+        - path_name: the name of the path
+        - length: the length of the path
+        - edits: the edits of the path
+        */
+        self.start_path(path_name, length);
+        for edit in edits {
+            self.add_edit(edit);
+        }
+    }
+
+    pub fn start_path(&mut self, path_name: &str, length: u64) {
+        /*
+        Starts counting the edits of a path, given one by one with add_edit
+        */
+        self.paths.push(PathDensity {
+            path_name: path_name.to_string(),
+            length,
+            windows: vec![(0, 0); length.div_ceil(self.window) as usize],
+        });
+    }
+
+    pub fn add_edit(&mut self, edit: &Edit) {
+        /*
+        Counts an edit of the last started path in its window
+        */
+        if let Some(path) = self.paths.last_mut() {
+            let index: usize = (edit.position / self.window) as usize;
+            if let Some((splits, merges)) = path.windows.get_mut(index) {
+                match edit.op {
+                    Operation::Split => *splits += 1,
+                    Operation::Merge => *merges += 1,
+                }
+            }
+        }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        /*
+        Writes the densities as bedGraph, with a track for splits, a track for merges and a track for all edits
+        */
+        for name in ["splits", "merges", "edits"] {
+            writeln!(
+                writer,
+                "track type=bedGraph name=\"{}\" description=\"{} per {} bp window\"",
+                name, name, self.window
+            )?;
+            for path in self.paths.iter() {
+                for (index, &(splits, merges)) in path.windows.iter().enumerate() {
+                    let start: u64 = index as u64 * self.window;
+                    let count: u64 = match name {
+                        "splits" => splits,
+                        "merges" => merges,
+                        _ => splits + merges,
+                    };
+                    writeln!(
+                        writer,
+                        "{}\t{}\t{}\t{}",
+                        path.path_name,
+                        start,
+                        (start + self.window).min(path.length),
+                        count
+                    )?;
+                }
+            }
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(position: u64, op: Operation) -> Edit {
+        Edit {
+            path: "x".to_string(),
+            position,
+            op,
+            node_a: "1".to_string(),
+            node_b: "2".to_string(),
+            breakpoint_a: 0,
+            breakpoint_b: 0,
+            strand_a: '+',
+            strand_b: '+',
+            offset_a: 0,
+            offset_b: 0,
+        }
+    }
+
+    #[test]
+    fn test_edit_density() {
+        let mut density: EditDensity = EditDensity::new(10);
+        density.add_path(
+            "x",
+            25,
+            &[
+                edit(2, Operation::Split),
+                edit(9, Operation::Merge),
+                edit(24, Operation::Split),
+            ],
+        );
+        let mut output: Vec<u8> = Vec::new();
+        density.write(&mut output).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(lines.len(), 12);
+        assert!(lines[0].starts_with("track type=bedGraph name=\"splits\""));
+        assert_eq!(lines[1], "x\t0\t10\t1");
+        assert_eq!(lines[3], "x\t20\t25\t1");
+        assert_eq!(lines[5], "x\t0\t10\t1");
+        assert_eq!(lines[9], "x\t0\t10\t2");
+    }
+}
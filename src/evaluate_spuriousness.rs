@@ -1,14 +1,58 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use crate::index_gfa_file::GfaIndex;
+use crate::read_gfa_file::GfaReader;
+use crate::segment_paths::PathSegmentation;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::io::{self, BufRead};
+use std::str::FromStr;
 // We want to check if some positions are spurious breakpoints.
-// A spurious breakpoint is a position where the node before has a single outgoing edge and the node after has a single incoming edge.
-// We can check this by computing the number of preceding and following neigbors of each node.
-// Here, we will list nodes ID per path that has spuriousness issues
-// We can then iterate in the next step over the path and check if nodes identified as spurious breakpoints are in the path.
-// If they are in, we can get the position in the path, which will gives us a series of spurious breakpoints per path
+// A spurious breakpoint is a junction between two nodes where nothing else can happen: the end of the node before
+// is only linked to the node after, and the end of the node after is only linked to the node before.
+// Graphs are bidirected: each node has two ends, '+' for the end of its forward strand and '-' for its start,
+// and a link joins the end of a node the path leaves to the end of the node the path enters.
+// A link from a+ to b+ joins the end of a to the start of b, a link from a+ to b- joins the end of a to the end of b.
+// Links are read from GFA1 L-lines and GFA2 dovetail E-lines.
+// Spurious junctions are listed in both directions of traversal, as (node, orientation, next node, orientation),
+// so they can be looked up directly while reading paths.
+// Spuriousness can also be computed on the subgraph induced by the compared paths, made of the links joining node ends they go through,
+// so that links used only by haplotypes missing from the other graph do not hide spurious breakpoints.
+// In the paths mode, only links between consecutive nodes of the paths are kept,
+// and a node end where a path starts or ends is never spurious, as merging its node with the next one would cut the path.
 
-pub fn spurious_breakpoints(file_path: &str) -> io::Result<Vec<String>> {
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpuriousMode {
+    // Node ends are only linked to each other by the links of the graph
+    #[default]
+    Topology,
+    // Node ends are only followed by each other in the paths, and no path starts or ends on them
+    Paths,
+}
+
+impl FromStr for SpuriousMode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<SpuriousMode, String> {
+        match name {
+            "topology" => Ok(SpuriousMode::Topology),
+            "paths" => Ok(SpuriousMode::Paths),
+            _ => Err(format!(
+                "unknown spurious mode {} (expected topology or paths)",
+                name
+            )),
+        }
+    }
+}
+
+// A junction between two consecutive nodes of a path: node, orientation, next node, orientation
+pub type Junction = (String, char, String, char);
+
+// Node ends as keys and the node ends they are linked to as values
+type Neighbours = HashMap<(String, char), Vec<(String, char)>>;
+
+// A node name and its orientation, as read in a link
+pub type NodeReference<'a> = (&'a str, char);
+
+pub fn spurious_breakpoints(file_path: &str) -> io::Result<HashSet<Junction>> {
     /*
     Computes spurious breakpoints in the graph
 
@@ -16,67 +60,227 @@ pub fn spurious_breakpoints(file_path: &str) -> io::Result<Vec<String>> {
     - file_path: a string with the path to the GFA file
 
     Returns:
-    - spurious_nodes: a vector of spurious node IDs as values
+    - the spurious junctions, in both directions of traversal
+    */
+    Ok(filter_spurious(&graph_links(file_path)?))
+}
+
+fn graph_links(file_path: &str) -> io::Result<Neighbours> {
+    /*
+    Reads the links of the graph, from L-lines and dovetail E-lines
+
+    Arguments:
+    - file_path: a string with the path to the GFA file
+
+    Returns:
+    - node ends as keys and the node ends they are linked to as values
     */
-    let file: File = File::open(file_path)?;
-    let mut reader: BufReader<File> = BufReader::new(file);
-    let mut seq_predecessors: HashMap<String, Vec<String>> = HashMap::new();
-    let mut seq_successors: HashMap<String, Vec<String>> = HashMap::new();
+    let mut reader: GfaReader = GfaReader::open(file_path)?;
+    let mut neighbours: HashMap<(String, char), Vec<(String, char)>> = HashMap::new();
 
     let mut line: String = String::new();
     while reader.read_line(&mut line)? > 0 {
-        let columns: Vec<&str> = line.split('\t').collect();
-        if let Some(first_char) = line.chars().next() {
-            if first_char == 'E' {
-                // In the case of an E-line, we store the predecessor and successor nodes
-                let predecessor: String = String::from(columns[1]);
-                let successor: String = String::from(columns[3]);
-
-                add_relation(
-                    &mut seq_predecessors,
-                    successor.clone(),
-                    predecessor.clone(),
-                );
-                add_relation(&mut seq_successors, predecessor, successor);
+        let columns: Vec<&str> = line.trim_end_matches(['\n', '\r']).split('\t').collect();
+        if columns[0] == "L" && columns.len() > 4 {
+            // In the case of an L-line, the path leaves the first node and enters the second one
+            add_link(
+                &mut neighbours,
+                (columns[1], orientation(columns[2])?),
+                (columns[3], orientation(columns[4])?),
+            );
+        }
+        if columns[0] == "E" && columns.len() > 7 {
+            // In the case of a GFA2 E-line, only dovetail edges are links
+            if let Some((from, to)) = dovetail(&columns)? {
+                add_link(&mut neighbours, from, to);
             }
-            line.clear(); // Clear the line buffer for the next read
         }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    Ok(neighbours)
+}
+
+pub fn subgraph_spurious_breakpoints(
+    file_path: &str,
+    index: &GfaIndex,
+    paths: &[String],
+    mode: SpuriousMode,
+) -> io::Result<HashSet<Junction>> {
+    /*
+    Computes spurious breakpoints on the subgraph induced by some paths
+    In the topology mode, the subgraph holds the links of the graph joining two node ends the paths go through,
+    in the paths mode, it only holds the links between consecutive nodes of the paths
+    Links used only by other paths are ignored, so that the result does not depend on paths missing from the other graph
+
+    Arguments:
+    - file_path: a string with the path to the GFA file
+    - index: the index of the GFA file
+    - paths: the names of the paths inducing the subgraph, usually the paths found in both graphs
+    - mode: in the paths mode, links are read from the paths and node ends where a path starts or ends are not spurious
+
+    Returns:
+    - the spurious junctions, in both directions of traversal
+    */
+    // Node ends as keys and the node ends paths continue into as values
+    let mut path_links: HashMap<(String, char), Vec<(String, char)>> = HashMap::new();
+    // Node ends paths enter or leave a node by
+    let mut traversed: HashSet<(String, char)> = HashSet::new();
+    // Node ends where a path starts or ends
+    let mut path_ends: HashSet<(String, char)> = HashSet::new();
+    for path_name in paths {
+        let segmentation: PathSegmentation = PathSegmentation::read(file_path, index, path_name)?;
+        for pair in segmentation.nodes.windows(2) {
+            let ((from, from_strand, _), (to, to_strand, _)) = (&pair[0], &pair[1]);
+            add_link(&mut path_links, (from, *from_strand), (to, *to_strand));
+            traversed.insert((from.clone(), *from_strand));
+            traversed.insert((to.clone(), flip(*to_strand)));
+        }
+        // The path enters its first node by its start if forward, and leaves its last node by its end if forward
+        if let (Some((first, first_strand, _)), Some((last, last_strand, _))) =
+            (segmentation.nodes.first(), segmentation.nodes.last())
+        {
+            path_ends.insert((first.clone(), flip(*first_strand)));
+            path_ends.insert((last.clone(), *last_strand));
+        }
+    }
+    let mut spurious_junctions: HashSet<Junction>;
+    if mode == SpuriousMode::Paths {
+        spurious_junctions = filter_spurious(&path_links);
+        spurious_junctions.retain(|(node, strand, next, next_strand)| {
+            !path_ends.contains(&(node.clone(), *strand))
+                && !path_ends.contains(&(next.clone(), flip(*next_strand)))
+        });
+    } else {
+        // We keep the links of the graph between node ends the paths go through, even if no path uses them
+        let mut neighbours: Neighbours = graph_links(file_path)?;
+        neighbours.retain(|node_end, _| traversed.contains(node_end));
+        for linked in neighbours.values_mut() {
+            linked.retain(|node_end| traversed.contains(node_end));
+        }
+        spurious_junctions = filter_spurious(&neighbours);
+    }
+    Ok(spurious_junctions)
+}
+
+pub fn dovetail<'a>(
+    columns: &[&'a str],
+) -> io::Result<Option<(NodeReference<'a>, NodeReference<'a>)>> {
+    /*
+    Reads a GFA2 E-line as a link, given as a traversal from a node to the next one
+
+    Arguments:
+    - columns: the columns of the E-line
+
+    Returns:
+    - the node the path leaves and the node it enters, with their orientations, or None if the edge is not a dovetail
+    */
+    // Segment references hold their orientation
+    let (node1, strand1) = reference(columns[2])?;
+    let (node2, strand2) = reference(columns[3])?;
+    // The overlap is at the start (beg = 0) or at the end (end = length$) of each segment, on its forward strand
+    let (start1, end1): (bool, bool) = (columns[4] == "0", columns[5].ends_with('$'));
+    let (start2, end2): (bool, bool) = (columns[6] == "0", columns[7].ends_with('$'));
+    if (start1 && end1) || (start2 && end2) {
+        // A segment covered by the overlap is contained in the other one
+        return Ok(None);
+    }
+    // The path leaves a segment by the overlap if it is at its end in the orientation of the reference
+    let leaves1: bool = if strand1 == '+' { end1 } else { start1 };
+    let enters2: bool = if strand2 == '+' { start2 } else { end2 };
+    let enters1: bool = if strand1 == '+' { start1 } else { end1 };
+    let leaves2: bool = if strand2 == '+' { end2 } else { start2 };
+    if leaves1 && enters2 {
+        Ok(Some(((node1, strand1), (node2, strand2))))
+    } else if enters1 && leaves2 {
+        // The path goes from the second segment to the first one, we read the link on the reverse strand
+        Ok(Some(((node1, flip(strand1)), (node2, flip(strand2)))))
+    } else {
+        // Else, the overlap is internal to a segment and the edge is not a link
+        Ok(None)
+    }
+}
+
+fn orientation(column: &str) -> io::Result<char> {
+    /*
+    Parses the orientation of a node in a link
+    */
+    match column {
+        "+" => Ok('+'),
+        "-" => Ok('-'),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid link orientation '{}'", column),
+        )),
+    }
+}
+
+fn reference(column: &str) -> io::Result<(&str, char)> {
+    /*
+    Splits a GFA2 segment reference (such as 12+) in node name and orientation
+    */
+    match column.char_indices().last() {
+        Some((index, strand)) if strand == '+' || strand == '-' => Ok((&column[..index], strand)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("segment reference '{}' has no orientation", column),
+        )),
     }
-    Ok(filter_spurious(seq_predecessors, seq_successors))
 }
 
-fn filter_spurious(
-    seq_predecessors: HashMap<String, Vec<String>>,
-    seq_successors: HashMap<String, Vec<String>>,
-) -> Vec<String> {
+pub fn flip(strand: char) -> char {
+    if strand == '+' {
+        '-'
+    } else {
+        '+'
+    }
+}
+
+fn add_link(
+    neighbours: &mut HashMap<(String, char), Vec<(String, char)>>,
+    from: (&str, char),
+    to: (&str, char),
+) {
+    /*
+    Adds a link, given as a traversal from a node to the next one, between the two node ends it joins
+
+    Arguments:
+    - neighbours: node ends as keys and the node ends they are linked to as values
+    - from: the node the path leaves, and its orientation
+    - to: the node the path enters, and its orientation
+    */
+    // The path leaves the first node by its end if forward, enters the next node by its start if forward
+    let from_end: (String, char) = (from.0.to_string(), from.1);
+    let to_end: (String, char) = (to.0.to_string(), flip(to.1));
+    add_relation(neighbours, to_end.clone(), from_end.clone());
+    add_relation(neighbours, from_end, to_end);
+}
+
+fn filter_spurious(neighbours: &HashMap<(String, char), Vec<(String, char)>>) -> HashSet<Junction> {
     /*
-    Filters spurious breakpoints from the predecessors and successors HashMaps
-    A spurious breakpoint is a position where the node before has a single outgoing edge and the node after has a single incoming edge
+    Filters spurious breakpoints from the links between node ends
+    A spurious breakpoint joins two node ends that are only linked to each other
+    Junctions of a node with itself are never spurious, as the node can't be merged with itself
 
     Arguments:
-    - seq_predecessors: a HashMap with the node names as keys and the predecessors as values
-    - seq_successors: a HashMap with the node names as keys and the successors as values
+    - neighbours: node ends as keys and the node ends they are linked to as values
 
     Returns:
-    - spurious_nodes: a vector of spurious node IDs
+    - the spurious junctions, in both directions of traversal
      */
-    let mut spurious_nodes: Vec<String> = Vec::new();
-    for (node, successors) in seq_successors.iter() {
-        if successors.len() == 1 {
-            let succ: &String = &successors[0];
-            if seq_predecessors.contains_key(succ) {
-                if seq_predecessors.get(succ).unwrap().len() == 1
-                    && seq_predecessors.get(succ).unwrap()[0] == *node
-                {
-                    spurious_nodes.push(succ.clone());
-                }
+    let mut spurious_junctions: HashSet<Junction> = HashSet::new();
+    for ((node, end), linked) in neighbours.iter() {
+        if let [(next, next_end)] = linked.as_slice() {
+            if next != node && neighbours[&(next.clone(), *next_end)].len() == 1 {
+                // Leaving the node by this end means going forward if it is the '+' end,
+                // and entering the next node by its '-' end (its start) means going forward
+                spurious_junctions.insert((node.clone(), *end, next.clone(), flip(*next_end)));
             }
         }
     }
-    spurious_nodes
+    spurious_junctions
 }
 
-fn add_relation(links: &mut HashMap<String, Vec<String>>, value: String, key: String) {
+fn add_relation<T: Clone + Eq + Hash>(links: &mut HashMap<T, Vec<T>>, value: T, key: T) {
     /*
     Function to add a relation between two nodes in a HashMap
 
@@ -86,9 +290,7 @@ fn add_relation(links: &mut HashMap<String, Vec<String>>, value: String, key: St
     - key: the key of the relation
      */
     if links.contains_key(&key) {
-        if links.get_mut(&key).unwrap().contains(&value) {
-            return;
-        } else {
+        if !links.get_mut(&key).unwrap().contains(&value) {
             links.get_mut(&key).unwrap().push(value.clone());
         }
     } else {
@@ -99,71 +301,214 @@ fn add_relation(links: &mut HashMap<String, Vec<String>>, value: String, key: St
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index_gfa_file::parse_gfa;
+
+    fn junction(node: &str, strand: char, next: &str, next_strand: char) -> Junction {
+        (node.to_string(), strand, next.to_string(), next_strand)
+    }
+
+    fn links(links: &[(&str, char, &str, char)]) -> HashMap<(String, char), Vec<(String, char)>> {
+        let mut neighbours: HashMap<(String, char), Vec<(String, char)>> = HashMap::new();
+        for &(from, from_strand, to, to_strand) in links {
+            add_link(&mut neighbours, (from, from_strand), (to, to_strand));
+        }
+        neighbours
+    }
 
     #[test]
     fn test_filter_single() {
-        let mut predecessors: HashMap<String, Vec<String>> = HashMap::new();
-        let mut successors: HashMap<String, Vec<String>> = HashMap::new();
-        predecessors.insert("1".to_string(), vec!["2".to_string()]);
-        successors.insert("2".to_string(), vec!["1".to_string()]);
-        let spurious_nodes: Vec<String> = filter_spurious(predecessors, successors);
-        assert_eq!(spurious_nodes, vec!["1".to_string()]);
+        let spurious_junctions: HashSet<Junction> =
+            filter_spurious(&links(&[("2", '+', "1", '+')]));
+        assert_eq!(
+            spurious_junctions,
+            HashSet::from([junction("2", '+', "1", '+'), junction("1", '-', "2", '-')])
+        );
     }
 
     #[test]
     fn test_filter_none() {
-        let predecessors: HashMap<String, Vec<String>> = HashMap::new();
-        let successors: HashMap<String, Vec<String>> = HashMap::new();
-        let spurious_nodes: Vec<String> = filter_spurious(predecessors, successors);
-        assert_eq!(spurious_nodes, Vec::<String>::new());
+        let spurious_junctions: HashSet<Junction> = filter_spurious(&HashMap::new());
+        assert_eq!(spurious_junctions, HashSet::new());
     }
 
     #[test]
     fn test_filter_empty_succ() {
-        let mut predecessors: HashMap<String, Vec<String>> = HashMap::new();
-        let mut successors: HashMap<String, Vec<String>> = HashMap::new();
-        predecessors.insert("1".to_string(), vec!["2".to_string()]);
-        predecessors.insert("3".to_string(), vec!["2".to_string()]);
-        successors.insert("2".to_string(), vec!["1".to_string(), "3".to_string()]);
-        let spurious_nodes: Vec<String> = filter_spurious(predecessors, successors);
-        assert_eq!(spurious_nodes, Vec::<String>::new());
+        // 2 is followed by 1 or 3
+        let spurious_junctions: HashSet<Junction> =
+            filter_spurious(&links(&[("2", '+', "1", '+'), ("2", '+', "3", '+')]));
+        assert_eq!(spurious_junctions, HashSet::new());
     }
 
     #[test]
     fn test_filter_empty_preds() {
-        let mut predecessors: HashMap<String, Vec<String>> = HashMap::new();
-        let mut successors: HashMap<String, Vec<String>> = HashMap::new();
-        predecessors.insert("1".to_string(), vec!["2".to_string(), "3".to_string()]);
-        successors.insert("2".to_string(), vec!["1".to_string()]);
-        successors.insert("3".to_string(), vec!["1".to_string()]);
-        let spurious_nodes: Vec<String> = filter_spurious(predecessors, successors);
-        assert_eq!(spurious_nodes, Vec::<String>::new());
+        // 1 is preceded by 2 or 3
+        let spurious_junctions: HashSet<Junction> =
+            filter_spurious(&links(&[("2", '+', "1", '+'), ("3", '+', "1", '+')]));
+        assert_eq!(spurious_junctions, HashSet::new());
     }
 
     #[test]
     fn test_filter_preds() {
-        let mut predecessors: HashMap<String, Vec<String>> = HashMap::new();
-        let mut successors: HashMap<String, Vec<String>> = HashMap::new();
-        predecessors.insert("1".to_string(), vec!["2".to_string()]);
-        predecessors.insert("2".to_string(), vec!["3".to_string(), "4".to_string()]);
-        successors.insert("2".to_string(), vec!["1".to_string()]);
-        successors.insert("3".to_string(), vec!["2".to_string()]);
-        successors.insert("4".to_string(), vec!["2".to_string()]);
-        let spurious_nodes: Vec<String> = filter_spurious(predecessors, successors);
-        assert_eq!(spurious_nodes, vec!["1".to_string()]);
+        // 2 is preceded by 3 or 4, but only followed by 1
+        let spurious_junctions: HashSet<Junction> = filter_spurious(&links(&[
+            ("2", '+', "1", '+'),
+            ("3", '+', "2", '+'),
+            ("4", '+', "2", '+'),
+        ]));
+        assert_eq!(
+            spurious_junctions,
+            HashSet::from([junction("2", '+', "1", '+'), junction("1", '-', "2", '-')])
+        );
     }
 
     #[test]
     fn test_filter_succs() {
-        let mut predecessors: HashMap<String, Vec<String>> = HashMap::new();
-        let mut successors: HashMap<String, Vec<String>> = HashMap::new();
-        predecessors.insert("1".to_string(), vec!["3".to_string()]);
-        predecessors.insert("2".to_string(), vec!["3".to_string()]);
-        predecessors.insert("3".to_string(), vec!["4".to_string()]);
-        successors.insert("4".to_string(), vec!["3".to_string()]);
-        successors.insert("3".to_string(), vec!["1".to_string(), "2".to_string()]);
-        let spurious_nodes: Vec<String> = filter_spurious(predecessors, successors);
-        assert_eq!(spurious_nodes, vec!["3".to_string()]);
+        // 3 is followed by 1 or 2, but only preceded by 4
+        let gfa_path = std::env::temp_dir().join(format!("{}_succs.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        std::fs::write(
+            gfa_path,
+            "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nL\t4\t+\t3\t+\t0M\nL\t3\t+\t1\t+\t0M\nL\t3\t+\t2\t+\t0M\n",
+        )
+        .unwrap();
+        let spurious_junctions: HashSet<Junction> =
+            filter_spurious(&graph_links(gfa_path).unwrap());
+        assert_eq!(
+            spurious_junctions,
+            HashSet::from([junction("4", '+', "3", '+'), junction("3", '-', "4", '-')])
+        );
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+
+    #[test]
+    fn test_filter_orientation() {
+        // The end of 1 is linked to the end of 2, and two other nodes enter 2 by its start
+        let spurious_junctions: HashSet<Junction> = filter_spurious(&links(&[
+            ("1", '+', "2", '-'),
+            ("3", '+', "2", '+'),
+            ("4", '+', "2", '+'),
+        ]));
+        assert_eq!(
+            spurious_junctions,
+            HashSet::from([junction("1", '+', "2", '-'), junction("2", '+', "1", '-')])
+        );
+        // Linking both ends of 2 to the end of 1 leaves the end of 1 with two neighbours
+        let spurious_junctions: HashSet<Junction> =
+            filter_spurious(&links(&[("1", '+', "2", '-'), ("1", '+', "2", '+')]));
+        assert_eq!(spurious_junctions, HashSet::new());
+    }
+
+    #[test]
+    fn test_spurious_breakpoints_gfa2() {
+        let gfa_path = std::env::temp_dir().join(format!("{}_spurious.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        // 1 is followed by 2 (overlap at the end of 1), 3 is followed by 1 (overlap at the start of 1)
+        std::fs::write(
+            gfa_path,
+            "H\tVN:Z:2.0\nS\t1\t4\tACGT\nS\t2\t2\tAC\nS\t3\t2\tGT\nE\t*\t1+\t2+\t4$\t4$\t0\t0\t0M\nE\t*\t1+\t3+\t0\t0\t2$\t2$\t0M\n",
+        )
+        .unwrap();
+        let spurious_junctions: HashSet<Junction> = spurious_breakpoints(gfa_path).unwrap();
+        assert_eq!(
+            spurious_junctions,
+            HashSet::from([
+                junction("1", '+', "2", '+'),
+                junction("2", '-', "1", '-'),
+                junction("3", '+', "1", '+'),
+                junction("1", '-', "3", '-'),
+            ])
+        );
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+
+    #[test]
+    fn test_dovetail() {
+        let edge = |line: &'static str| dovetail(&line.split('\t').collect::<Vec<&str>>()).unwrap();
+        // The end of 1 overlaps the start of 2, or the end of 2 when 2 is reversed
+        assert_eq!(
+            edge("E\t*\t1+\t2+\t4$\t4$\t0\t0\t0M"),
+            Some((("1", '+'), ("2", '+')))
+        );
+        assert_eq!(
+            edge("E\t*\t1+\t2-\t4$\t4$\t2$\t2$\t0M"),
+            Some((("1", '+'), ("2", '-')))
+        );
+        // The end of 3 overlaps the start of 1
+        assert_eq!(
+            edge("E\t*\t1+\t3+\t0\t0\t2$\t2$\t0M"),
+            Some((("1", '-'), ("3", '-')))
+        );
+        // Containment and internal overlaps are not links
+        assert_eq!(edge("E\t*\t1+\t2+\t0\t2\t0\t2$\t2M"), None);
+        assert_eq!(edge("E\t*\t1+\t2+\t1\t2\t0\t1\t1M"), None);
+        assert_eq!(edge("E\t*\t1+\t2+\t4$\t4$\t2$\t2$\t0M"), None);
+    }
+
+    #[test]
+    fn test_subgraph_spurious_breakpoints() {
+        let gfa_path =
+            std::env::temp_dir().join(format!("{}_path_spurious.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        // No path uses the link from 1 to 4, and z starts on 1 where x and y go through 3
+        std::fs::write(
+            gfa_path,
+            "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nL\t1\t+\t2\t+\t0M\nL\t1\t+\t4\t+\t0M\nL\t3\t+\t1\t+\t0M\nP\tx\t3+,1+,2+\t*\nP\ty\t2-,1-,3-\t*\nP\tz\t1+,2+\t*\n",
+        )
+        .unwrap();
+        let index: GfaIndex = parse_gfa(gfa_path).unwrap();
+        assert_eq!(
+            spurious_breakpoints(gfa_path).unwrap(),
+            HashSet::from([junction("3", '+', "1", '+'), junction("1", '-', "3", '-')])
+        );
+        let paths: Vec<String> = vec!["x".to_string(), "y".to_string(), "z".to_string()];
+        let spurious_junctions: HashSet<Junction> =
+            subgraph_spurious_breakpoints(gfa_path, &index, &paths, SpuriousMode::Paths).unwrap();
+        assert_eq!(
+            spurious_junctions,
+            HashSet::from([junction("1", '+', "2", '+'), junction("2", '-', "1", '-')])
+        );
+        // Without the link from 1 to 4, used by no path, the junction of 1 and 2 is spurious
+        let spurious_junctions: HashSet<Junction> =
+            subgraph_spurious_breakpoints(gfa_path, &index, &paths, SpuriousMode::Topology)
+                .unwrap();
+        assert_eq!(spurious_junctions.len(), 4);
+        assert!(spurious_junctions.contains(&junction("1", '+', "2", '+')));
+        // Without z, no path starts on 1
+        let spurious_junctions: HashSet<Junction> =
+            subgraph_spurious_breakpoints(gfa_path, &index, &paths[..2], SpuriousMode::Paths)
+                .unwrap();
+        assert_eq!(spurious_junctions.len(), 4);
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+
+    #[test]
+    fn test_subgraph_spurious_modes() {
+        let gfa_path =
+            std::env::temp_dir().join(format!("{}_mode_spurious.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        // Only x is shared, z is an extra haplotype through 4, and no path uses the link from 1- to 3+
+        std::fs::write(
+            gfa_path,
+            "S\t0\tA\nS\t1\tC\nS\t2\tG\nS\t3\tT\nS\t4\tA\nL\t0\t+\t1\t+\t0M\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t1\t+\t4\t+\t0M\nL\t1\t-\t3\t+\t0M\nP\tx\t0+,1+,2+,3+\t*\nP\tz\t1+,4+\t*\n",
+        )
+        .unwrap();
+        let index: GfaIndex = parse_gfa(gfa_path).unwrap();
+        assert_eq!(spurious_breakpoints(gfa_path).unwrap(), HashSet::new());
+        let paths: Vec<String> = vec!["x".to_string()];
+        // The link of z is dropped, the unused link between 1 and 3 still joins 0 and 1, and 2 and 3
+        let spurious_junctions: HashSet<Junction> =
+            subgraph_spurious_breakpoints(gfa_path, &index, &paths, SpuriousMode::Topology)
+                .unwrap();
+        assert_eq!(
+            spurious_junctions,
+            HashSet::from([junction("1", '+', "2", '+'), junction("2", '-', "1", '-')])
+        );
+        // Only consecutive nodes of x are linked
+        let spurious_junctions: HashSet<Junction> =
+            subgraph_spurious_breakpoints(gfa_path, &index, &paths, SpuriousMode::Paths).unwrap();
+        assert_eq!(spurious_junctions.len(), 6);
+        assert!(spurious_junctions.contains(&junction("2", '+', "3", '+')));
+        std::fs::remove_file(gfa_path).unwrap();
     }
 
     #[test]
@@ -193,11 +538,13 @@ mod tests {
 
     #[test]
     fn test_add_and_spurious() {
-        let mut predecessors: HashMap<String, Vec<String>> = HashMap::new();
-        let mut successors: HashMap<String, Vec<String>> = HashMap::new();
-        add_relation(&mut predecessors, "2".to_string(), "1".to_string());
-        add_relation(&mut successors, "1".to_string(), "2".to_string());
-        let spurious_nodes: Vec<String> = filter_spurious(predecessors, successors);
-        assert_eq!(spurious_nodes, vec!["1".to_string()]);
+        let mut neighbours: HashMap<(String, char), Vec<(String, char)>> = HashMap::new();
+        add_link(&mut neighbours, ("1", '+'), ("2", '+'));
+        assert_eq!(
+            neighbours[&("1".to_string(), '+')],
+            vec![("2".to_string(), '-')]
+        );
+        let spurious_junctions: HashSet<Junction> = filter_spurious(&neighbours);
+        assert!(spurious_junctions.contains(&junction("1", '+', "2", '+')));
     }
 }
@@ -1,151 +1,485 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use crate::normalize_path_names::PathNaming;
+use crate::read_gfa_file::GfaReader;
+use crate::store_gfa_index;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead};
 
-pub fn index_gfa(
-    file_path: &str,
-    hard_match: bool,
-) -> io::Result<(
-    HashMap<String, u64>,
-    HashMap<String, u64>,
-    HashMap<String, u64>,
-    HashMap<String, char>,
-)> {
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GfaIndex {
+    // Node names as keys and sequence lengths as values
+    pub seq_lengths: HashMap<String, u64>,
+    // Path names as keys and positions of the lines describing the path (in path order) as values
+    pub path_positions: HashMap<String, Vec<u64>>,
+    // Path names as keys and lengths of the paths (in base pairs) as values
+    pub path_lengths: HashMap<String, u64>,
+    // Path names as keys and types of the lines describing the paths (P, W, O or U) as values
+    pub path_types: HashMap<String, char>,
+    // Path names as keys and gaps between W-lines fragments as values:
+    // (position on the path, end of the previous fragment, start of the next fragment)
+    pub path_gaps: HashMap<String, Vec<(u64, u64, u64)>>,
+}
+
+pub fn index_gfa(file_path: &str, naming: &PathNaming) -> io::Result<(GfaIndex, Option<String>)> {
     /*
-    Given a file path, this function reads the GFA file and returns two HashMaps:
+    Given a file path, this function returns the index of the GFA file, with path names normalised
+    with the given naming rules, so that paths can be paired between graphs
+    If a valid index file was written next to the GFA file (with the index command), it is read
+    instead of parsing the GFA file
+    The index file is ignored if it can't be read or does not match the GFA file, and a warning is returned
+    */
+    let (index, warning): (GfaIndex, Option<String>) = match store_gfa_index::read_index(file_path)
+    {
+        Ok(Some(index)) => (index, None),
+        Ok(None) => (parse_gfa(file_path)?, None),
+        Err(error) => (
+            parse_gfa(file_path)?,
+            Some(format!("ignoring index file of {}: {}", file_path, error)),
+        ),
+    };
+    Ok((rename_paths(index, naming)?, warning))
+}
+
+pub fn parse_gfa(file_path: &str) -> io::Result<GfaIndex> {
+    /*
+    Given a file path, this function reads the GFA file (GFA1 or GFA2) and returns its index, made of HashMaps:
     - seq_lengths: a HashMap with the node names as keys and the sequence lengths as values
-    - path_positions: a HashMap with the path names as keys and the offset of the path description as values
+    - path_positions: a HashMap with the path names as keys and the positions of the path lines as values
     - path_lengths: a HashMap with the path names as keys and the number of nodes in the path as values
+    - path_types: a HashMap with the path names as keys and the type of line describing the path (P, W, O or U) as values
+    - path_gaps: a HashMap with the path names as keys and the gaps between fragments of the path as values
+    Path names are kept as they are written in the file (sample#haplotype#contig for W-lines)
+    In GFA2 files, named O-groups and U-groups are considered as paths
+    A haplotype may be split over several W-lines (same sample, haplotype and contig):
+    its fragments are ordered by their start coordinate and read one after the other as a single path
     */
-    let file = File::open(file_path)?;
-    let mut reader = BufReader::new(file);
+    let mut reader: GfaReader = GfaReader::open(file_path)?;
 
     let mut seq_lengths: HashMap<String, u64> = HashMap::new();
-    let mut path_positions: HashMap<String, u64> = HashMap::new();
+    let mut path_positions: HashMap<String, Vec<u64>> = HashMap::new();
     let mut path_types: HashMap<String, char> = HashMap::new();
+    // W-lines fragments of each path: (start, end, position of the line), coordinates may be omitted ('*')
+    let mut walk_fragments: HashMap<String, Vec<(Coordinate, Coordinate, u64)>> = HashMap::new();
+
+    // GFA version, given by the header; when absent, it is guessed from S-lines
+    let mut gfa2: Option<bool> = None;
 
     let mut line = String::new();
+    // Position of the line being read, given back to seek_path when reading paths
+    let mut line_position: u64 = reader.tell()?;
     while reader.read_line(&mut line)? > 0 {
         let columns: Vec<&str> = line.split('\t').collect();
         if let Some(first_char) = line.chars().next() {
+            if first_char == 'H' {
+                // The header may tell the GFA version (VN:Z:1.0 or VN:Z:2.0)
+                if let Some(version) = columns
+                    .iter()
+                    .find_map(|tag| tag.trim().strip_prefix("VN:Z:"))
+                {
+                    gfa2 = Some(version.starts_with('2'));
+                }
+            }
             if first_char == 'S' {
                 // In the case of an S-line, we store the node name and the sequence length
                 let node_name = String::from(columns[1]);
-                let sequence_length = columns[2].trim().len();
-                seq_lengths.insert(node_name, sequence_length as u64);
+                // GFA2 S-lines have an explicit length column before the sequence
+                let is_gfa2 = gfa2.unwrap_or_else(|| is_gfa2_segment(&columns));
+                let sequence_length = segment_length(&columns, is_gfa2)?;
+                seq_lengths.insert(node_name, sequence_length);
             }
             if first_char == 'W' {
-                // In the case of a W-line, we store the path name and the offset of the path description
+                // In the case of a W-line, we store the path name and the position of the line
                 // When processing paths, we can match paths in the path_positions HashMap
-                // Then start reading the file from there and go with a buffer to read node by node the path
-                let path_name = if hard_match {
-                    columns[1].to_string() + "#" + columns[2] + "#" + columns[3]
-                } else {
-                    // Capitalize path name and remove trailing '#0'
-                    (columns[1].to_string() + "#" + columns[2] + "#" + columns[3])
-                    .to_ascii_uppercase()
-                    .trim_end_matches("#0")
-                    .to_string()
-                };
-                let offset = reader.seek(io::SeekFrom::Current(0))?
-                - (line.len() as u64
-                - columns[0].len() as u64
-                - columns[1].len() as u64
-                - columns[2].len() as u64
-                - columns[3].len() as u64
-                - columns[4].len() as u64
-                - columns[5].len() as u64
-                - 6);
-                path_positions.insert(path_name.clone(), offset + 1);
+                // Then seek to the line and go with a buffer to read node by node the path
+                let path_name = columns[1].to_string() + "#" + columns[2] + "#" + columns[3];
+                let start: Coordinate = parse_coordinate(columns[4], &path_name)?;
+                let end: Coordinate = parse_coordinate(columns[5], &path_name)?;
+                walk_fragments.entry(path_name.clone()).or_default().push((
+                    start,
+                    end,
+                    line_position,
+                ));
                 path_types.insert(path_name.clone(), 'W');
             }
             if first_char == 'P' {
-                // In the case of a P-line, we store the path name and the offset of the path description
+                // In the case of a P-line, we store the path name and the position of the line
                 // When processing paths, we can match paths in the path_positions HashMap
-                // Then start reading the file from there and go with a buffer to read node by node the path
-                let path_name = if hard_match {
-                    String::from(columns[1])
-                } else {
-                    // Capitalize path name and remove trailing '#0'
-                    String::from(columns[1])
-                    .to_ascii_uppercase()
-                    .trim_end_matches("#0")
-                    .to_string()
-                };
-                let offset = reader.seek(io::SeekFrom::Current(0))?
-                - (line.len() as u64 - columns[0].len() as u64 - columns[1].len() as u64 - 2);
-                path_positions.insert(path_name.clone(), offset);
+                // Then seek to the line and go with a buffer to read node by node the path
+                let path_name = String::from(columns[1]);
+                path_positions.insert(path_name.clone(), vec![line_position]);
                 path_types.insert(path_name.clone(), 'P');
             }
+            if (first_char == 'O' || first_char == 'U') && columns.len() > 2 && columns[1] != "*" {
+                // In the case of a GFA2 group, we consider it as a path if it is named
+                // Groups are read the same way as P-lines
+                let path_name = String::from(columns[1]);
+                path_positions.insert(path_name.clone(), vec![line_position]);
+                path_types.insert(path_name.clone(), first_char);
+            }
         }
         line.clear(); // Clear the line buffer for the next read
+        line_position = reader.tell()?;
+    }
+    // Fragments of walks are sorted by their start coordinate on the haplotype
+    // If a start coordinate is omitted, fragments are kept in the order of the file
+    let mut walk_coordinates: HashMap<String, Vec<(Coordinate, Coordinate)>> = HashMap::new();
+    for (path_name, mut fragments) in walk_fragments.into_iter() {
+        if fragments.iter().all(|(start, _, _)| start.is_some()) {
+            fragments.sort();
+        }
+        path_positions.insert(
+            path_name.clone(),
+            fragments.iter().map(|&(_, _, position)| position).collect(),
+        );
+        walk_coordinates.insert(
+            path_name,
+            fragments
+                .iter()
+                .map(|&(start, end, _)| (start, end))
+                .collect(),
+        );
     }
-    let path_lengths: HashMap<String, u64> = get_paths_lengths(file_path,path_positions.clone(),path_types.clone(),seq_lengths.clone()).unwrap();
+    let (path_lengths, path_gaps) = get_paths_lengths(
+        file_path,
+        &path_positions,
+        &path_types,
+        &seq_lengths,
+        &walk_coordinates,
+    )?;
 
-    Ok((seq_lengths, path_positions, path_lengths, path_types))
+    Ok(GfaIndex {
+        seq_lengths,
+        path_positions,
+        path_lengths,
+        path_types,
+        path_gaps,
+    })
 }
 
-fn get_paths_lengths(file_path: &str,path_positions: HashMap<String, u64>,path_types:HashMap<String, char>,seq_lengths:HashMap<String, u64>) -> Result<HashMap<String, u64>,io::Error> {
+pub fn read_sequences(
+    file_path: &str,
+    node_names: &HashSet<String>,
+) -> io::Result<HashMap<String, String>> {
+    /*
+    Reads the sequences of some nodes of a GFA file (GFA1 or GFA2)
+
+    Arguments:
+    - file_path: the path to the GFA file
+    - node_names: the names of the nodes whose sequences are read
 
-    let mut path_lengths: HashMap<String, u64> = HashMap::new();
-    let mut file: BufReader<File> = BufReader::new(File::open(file_path)?);
-    
-    // Buffer to read the two files
-    let mut buffer: [u8; 1] = [0; 1];
-    
-    for (path_name,path_pos) in path_positions.into_iter() {
-        file.seek(SeekFrom::Start(path_pos))?;
-        let mut path_length:u64 = 0;
-        loop {
-            let node = if path_types[path_name.as_str()] == 'P' {
-                read_next_p_node(&mut file, &mut buffer)
-            } else {
-                read_next_w_node(&mut file, &mut buffer)
-            };
-            if node.contains("\t") {
-                break;
+    Returns:
+    - a HashMap with the node names as keys and the sequences as values ('*' if the sequence is omitted)
+    */
+    let mut reader: GfaReader = GfaReader::open(file_path)?;
+    let mut sequences: HashMap<String, String> = HashMap::new();
+    let mut gfa2: Option<bool> = None;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let columns: Vec<&str> = line.trim_end_matches(['\n', '\r']).split('\t').collect();
+        if columns[0] == "H" {
+            if let Some(version) = columns.iter().find_map(|tag| tag.strip_prefix("VN:Z:")) {
+                gfa2 = Some(version.starts_with('2'));
             }
-            let sequence_length = seq_lengths.get(&node).unwrap();
-            path_length += sequence_length;
         }
-        path_lengths.insert(path_name.clone(), path_length as u64);
+        if columns[0] == "S" && columns.len() > 2 && node_names.contains(columns[1]) {
+            // GFA2 S-lines have an explicit length column before the sequence
+            let is_gfa2 = gfa2.unwrap_or_else(|| is_gfa2_segment(&columns));
+            let sequence: &str = columns
+                .get(if is_gfa2 { 3 } else { 2 })
+                .copied()
+                .unwrap_or("*");
+            sequences.insert(columns[1].to_string(), sequence.to_string());
+        }
+        line.clear(); // Clear the line buffer for the next read
     }
-    Ok(path_lengths)
+    Ok(sequences)
+}
 
+fn rename_paths(index: GfaIndex, naming: &PathNaming) -> io::Result<GfaIndex> {
+    /*
+    Replaces path names in the index by the keys given by the naming rules
+    Returns an error if several paths get the same key, as they could not be told apart
+    */
+    let mut renamed: GfaIndex = GfaIndex {
+        seq_lengths: index.seq_lengths,
+        path_positions: HashMap::new(),
+        path_lengths: HashMap::new(),
+        path_types: HashMap::new(),
+        path_gaps: HashMap::new(),
+    };
+    // Paths are renamed in a fixed order, so the same collision is reported on every run
+    let mut path_names: Vec<&String> = index.path_positions.keys().collect();
+    path_names.sort();
+    for path_name in path_names {
+        let key: String = naming.normalize(path_name);
+        if renamed.path_positions.contains_key(&key) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("path {} is named {} as another path", path_name, key),
+            ));
+        }
+        renamed
+            .path_positions
+            .insert(key.clone(), index.path_positions[path_name].clone());
+        renamed
+            .path_lengths
+            .insert(key.clone(), index.path_lengths[path_name]);
+        renamed
+            .path_types
+            .insert(key.clone(), index.path_types[path_name]);
+        match index.path_gaps.get(path_name) {
+            Some(gaps) => renamed.path_gaps.insert(key, gaps.clone()),
+            None => renamed.path_gaps.remove(&key),
+        };
+    }
+    Ok(renamed)
 }
 
+// A start or end coordinate of a W-line, None if omitted ('*')
+type Coordinate = Option<u64>;
 
-fn read_next_p_node(file: &mut BufReader<File>, buffer: &mut [u8; 1]) -> String {
+type PathLengths = (HashMap<String, u64>, HashMap<String, Vec<(u64, u64, u64)>>);
+
+fn get_paths_lengths(
+    file_path: &str,
+    path_positions: &HashMap<String, Vec<u64>>,
+    path_types: &HashMap<String, char>,
+    seq_lengths: &HashMap<String, u64>,
+    walk_coordinates: &HashMap<String, Vec<(Coordinate, Coordinate)>>,
+) -> io::Result<PathLengths> {
     /*
-     * Read the next node in the file, until a comma is found
-     * file: the file to read
-     * buffer: a buffer to read the file
-     */
-    let mut node = String::new();
-    while file.read(buffer).unwrap() > 0 {
-        if buffer[0] == b'+' || buffer[0] == b'-' {
-            break;
-        }
-        if buffer[0] != b',' {
-            node.push(buffer[0] as char);
+    Reads every path to compute its length, and the gaps between its fragments
+
+    Arguments:
+    - file_path: the path to the GFA file
+    - path_positions: a HashMap with the path names as keys and the positions of the path lines as values
+    - path_types: a HashMap with the path names as keys and the path types as values
+    - seq_lengths: a HashMap with the node names as keys and the sequence lengths as values
+    - walk_coordinates: a HashMap with the walk names as keys and the (start, end) of each fragment as values, None if omitted
+
+    Returns:
+    - path_lengths: a HashMap with the path names as keys and the lengths of the paths as values
+    - path_gaps: a HashMap with the path names as keys and the gaps in the paths as values
+    */
+    let mut path_lengths: HashMap<String, u64> = HashMap::new();
+    let mut path_gaps: HashMap<String, Vec<(u64, u64, u64)>> = HashMap::new();
+    let mut file: GfaReader = GfaReader::open(file_path)?;
+
+    for (path_name, positions) in path_positions.iter() {
+        let path_type: char = path_types[path_name.as_str()];
+        let mut path_length: u64 = 0;
+        for (fragment, &position) in positions.iter().enumerate() {
+            if fragment > 0 {
+                // Between two fragments, the previous one should end where the next one starts
+                // A gap can't be told if one of the coordinates is omitted
+                let (_, previous_end) = walk_coordinates[path_name][fragment - 1];
+                let (next_start, _) = walk_coordinates[path_name][fragment];
+                if let (Some(previous_end), Some(next_start)) = (previous_end, next_start) {
+                    if next_start > previous_end {
+                        path_gaps.entry(path_name.clone()).or_default().push((
+                            path_length,
+                            previous_end,
+                            next_start,
+                        ));
+                    }
+                }
+            }
+            file.seek_path(position, path_type)?;
+            while let Some((_, _, sequence_length)) =
+                file.read_next_segment(path_type, seq_lengths)?
+            {
+                path_length += sequence_length;
+            }
         }
+        path_lengths.insert(path_name.clone(), path_length);
     }
-    node
+    Ok((path_lengths, path_gaps))
 }
 
-fn read_next_w_node(file: &mut BufReader<File>, buffer: &mut [u8; 1]) -> String {
+fn parse_coordinate(column: &str, path_name: &str) -> io::Result<Coordinate> {
     /*
-     * Read the next node in the file, until a > or < is found
-     * file: the file to read
-     * buffer: a buffer to read the file
-     */
-    let mut node = String::new();
-    while file.read(buffer).unwrap() > 0 {
-        if buffer[0] == b'>' || buffer[0] == b'<' || buffer[0] == b'\t' {
-            break;
-        } else {
-            node.push(buffer[0] as char);
-        }
+    Parses a start or end coordinate of a W-line, None if it is omitted ('*')
+    */
+    if column.trim() == "*" {
+        return Ok(None);
+    }
+    column.trim().parse::<u64>().map(Some).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "invalid coordinate for walk {}: {}",
+                path_name,
+                column.trim()
+            ),
+        )
+    })
+}
+
+pub fn segment_length(columns: &[&str], is_gfa2: bool) -> io::Result<u64> {
+    /*
+    Computes the length of a segment from its S-line
+
+    Arguments:
+    - columns: the tab-separated columns of the S-line
+    - is_gfa2: true if the S-line is a GFA2 segment (S, name, length, sequence)
+
+    Returns:
+    - the length of the segment: the length column in GFA2, else the sequence length,
+      or the LN:i: tag when the sequence is omitted ('*')
+    */
+    let node_name: &str = columns[1];
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    if is_gfa2 {
+        return columns[2].trim().parse::<u64>().map_err(|_| {
+            invalid(format!(
+                "invalid length for segment {}: {}",
+                node_name,
+                columns[2].trim()
+            ))
+        });
+    }
+    let sequence: &str = columns.get(2).map_or("*", |sequence| sequence.trim());
+    if sequence != "*" {
+        return Ok(sequence.len() as u64);
+    }
+    match columns
+        .iter()
+        .skip(3)
+        .find_map(|tag| tag.trim().strip_prefix("LN:i:"))
+    {
+        Some(length) => length.parse::<u64>().map_err(|_| {
+            invalid(format!(
+                "invalid LN tag for segment {}: {}",
+                node_name, length
+            ))
+        }),
+        None => Err(invalid(format!(
+            "segment {} has no sequence and no LN tag, its length is unknown",
+            node_name
+        ))),
+    }
+}
+
+fn is_gfa2_segment(columns: &[&str]) -> bool {
+    /*
+    Guesses if an S-line is a GFA2 segment, when the header does not tell the version
+    GFA2 segments are S, name, length, sequence while GFA1 segments are S, name, sequence
+    A sequence can't be made of digits only, so a numeric third column followed by another column is a length
+    */
+    columns.len() > 3 && !columns[2].is_empty() && columns[2].bytes().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segment_paths::PathSegmentation;
+
+    #[test]
+    fn test_segment_length_sequence() {
+        assert_eq!(segment_length(&["S", "1", "ACGT\n"], false).unwrap(), 4);
+        assert_eq!(
+            segment_length(&["S", "1", "ACGT", "LN:i:4\n"], false).unwrap(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_segment_length_ln_tag() {
+        assert_eq!(
+            segment_length(&["S", "1", "*", "LN:i:12\n"], false).unwrap(),
+            12
+        );
+        assert_eq!(
+            segment_length(&["S", "1", "*", "RC:i:3", "LN:i:7"], false).unwrap(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_segment_length_missing() {
+        assert!(segment_length(&["S", "1", "*\n"], false).is_err());
+        assert!(segment_length(&["S", "1", "*", "LN:i:x"], false).is_err());
+    }
+
+    #[test]
+    fn test_walk_omitted_coordinates() {
+        let gfa_path = std::env::temp_dir().join(format!("{}_walks.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        // Fragments without coordinates are read in the order of the file, with no gap
+        std::fs::write(
+            gfa_path,
+            "S\t1\tACGT\nS\t2\tAC\nW\ts\t1\tc\t*\t*\t>1\nW\ts\t1\tc\t10\t*\t>2\n",
+        )
+        .unwrap();
+        let index: GfaIndex = parse_gfa(gfa_path).unwrap();
+        assert_eq!(index.path_lengths["s#1#c"], 6);
+        assert_eq!(index.path_positions["s#1#c"].len(), 2);
+        assert!(index.path_positions["s#1#c"][0] < index.path_positions["s#1#c"][1]);
+        assert!(!index.path_gaps.contains_key("s#1#c"));
+        assert!(parse_coordinate("x", "s#1#c").is_err());
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+
+    #[test]
+    fn test_rename_paths_collision() {
+        let gfa_path = std::env::temp_dir().join(format!("{}_rename.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        std::fs::write(gfa_path, "S\t1\tACGT\nP\tX\t1+\t*\nP\ty\t1+\t*\n").unwrap();
+        // y is mapped to x, which is normalised as the name of the path X
+        let naming: PathNaming = PathNaming {
+            hard: false,
+            delimiters: "#".to_string(),
+            pansn_fields: None,
+            mapping: HashMap::from([("y".to_string(), "x".to_string())]),
+        };
+        assert!(rename_paths(parse_gfa(gfa_path).unwrap(), &naming).is_err());
+        let naming: PathNaming = PathNaming {
+            mapping: HashMap::new(),
+            ..naming
+        };
+        let renamed: GfaIndex = rename_paths(parse_gfa(gfa_path).unwrap(), &naming).unwrap();
+        assert!(renamed.path_positions.contains_key("Y"));
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+
+    #[test]
+    fn test_segment_length_gfa2() {
+        assert_eq!(segment_length(&["S", "1", "25", "*\n"], true).unwrap(), 25);
+        assert!(is_gfa2_segment(&["S", "1", "25", "*\n"]));
+        assert!(!is_gfa2_segment(&["S", "1", "*", "LN:i:25\n"]));
+    }
+
+    #[test]
+    fn test_parse_gfa2() {
+        let gfa_path = std::env::temp_dir().join(format!("{}_index2.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        // The O-group p goes through the edge e1, which is skipped, and the unnamed group is not a path
+        std::fs::write(
+            gfa_path,
+            "H\tVN:Z:2.0\nS\t1\t4\tACGT\nS\t2\t3\t*\nS\t3\t2\tAC\nE\te1\t1+\t2+\t4$\t4$\t0\t0\t0M\nE\te2\t2+\t3-\t3$\t3$\t2$\t2$\t0M\nO\tp\t1+ e1+ 2+ 3-\nU\tu\t1 3 e2\nO\t*\t1+ 2+\n",
+        )
+        .unwrap();
+        let index: GfaIndex = parse_gfa(gfa_path).unwrap();
+        assert_eq!(
+            index.seq_lengths,
+            HashMap::from([
+                ("1".to_string(), 4),
+                ("2".to_string(), 3),
+                ("3".to_string(), 2)
+            ])
+        );
+        assert_eq!(
+            index.path_types,
+            HashMap::from([("p".to_string(), 'O'), ("u".to_string(), 'U')])
+        );
+        assert_eq!(index.path_lengths["p"], 9);
+        assert_eq!(index.path_lengths["u"], 6);
+        let segmentation: PathSegmentation = PathSegmentation::read(gfa_path, &index, "p").unwrap();
+        let nodes: Vec<(&str, char)> = segmentation
+            .nodes
+            .iter()
+            .map(|(node, strand, _)| (node.as_str(), *strand))
+            .collect();
+        assert_eq!(nodes, vec![("1", '+'), ("2", '+'), ("3", '-')]);
+        std::fs::remove_file(gfa_path).unwrap();
     }
-    node
 }
@@ -0,0 +1,32 @@
+// rs-pancat-compare computes the segmentation distance between pangenome graphs.
+// The library gives access to the indexes of GFA files, to path segmentations and to the edits between two graphs,
+// so that other tools can compare graphs without parsing the command line output.
+
+pub mod annotate_edit_lengths;
+pub mod common_coarsening;
+pub mod common_refinement;
+pub mod compute_distance;
+pub mod distance_matrix;
+pub mod edit_density;
+pub mod evaluate_spuriousness;
+pub mod index_gfa_file;
+pub mod local_to_global;
+pub mod normalize_graph;
+pub mod normalize_path_names;
+pub mod read_gfa_file;
+pub mod segment_paths;
+pub mod select_regions;
+pub mod store_gfa_index;
+pub mod verify_edits;
+pub mod write_report;
+
+pub use compute_distance::{
+    distance, edits, path_edits, path_intersection, DistanceOptions, DistanceReport, Edit,
+    EditCounts, EditIterator, Operation, PathEdits,
+};
+pub use distance_matrix::{distance_matrix, TreeMethod};
+pub use index_gfa_file::{index_gfa, parse_gfa, GfaIndex};
+pub use normalize_path_names::PathNaming;
+pub use segment_paths::PathSegmentation;
+pub use select_regions::Regions;
+pub use write_report::OutputFormat;
@@ -1,3 +1,5 @@
+use crate::evaluate_spuriousness::dovetail;
+use crate::read_gfa_file::GfaReader;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
@@ -8,7 +10,7 @@ pub fn local_to_global(
     distance_file: &str,
 ) -> io::Result<()> {
     /*
-    Given two file paths, this function reads the GFA file and the TSV file
+    Given two file paths, this function reads the GFA files (edges from L-lines, or E-lines in GFA2) and the TSV file
     It writes a new file in stdio with unique breakpoints
     Merges and splits must be handled differently: each category should be reported on a specific graph:
     Splits on A, Merges on B
@@ -16,9 +18,8 @@ pub fn local_to_global(
 
     // Graph A
     // We init the edges collection
-    let file = File::open(graph_a_file)?;
-    let mut reader = BufReader::new(file);
-    let mut edges_a_collection: HashMap<[String; 2],Vec<String>> = HashMap::new();
+    let mut reader: GfaReader = GfaReader::open(graph_a_file)?;
+    let mut edges_a_collection: HashMap<[String; 2], Vec<String>> = HashMap::new();
     let mut line = String::new();
 
     while reader.read_line(&mut line)? > 0 {
@@ -26,9 +27,21 @@ pub fn local_to_global(
         if let Some(first_char) = line.chars().next() {
             if first_char == 'L' {
                 // In the case of an L-line, we store the two node names and init an empty vec
-                let node_x_name = String::from(columns[1].to_owned()+columns[2]);
-                let node_y_name = String::from(columns[3].to_owned()+columns[4]);
-                edges_a_collection.insert([node_x_name,node_y_name], Vec::new());
+                let node_x_name = columns[1].to_owned() + columns[2];
+                let node_y_name = columns[3].to_owned() + columns[4];
+                edges_a_collection.insert([node_x_name, node_y_name], Vec::new());
+            }
+            if first_char == 'E' && columns.len() > 7 {
+                // In the case of a GFA2 E-line, only dovetail edges are links, read as for spurious breakpoints
+                if let Some(((node_x, strand_x), (node_y, strand_y))) = dovetail(&columns)? {
+                    edges_a_collection.insert(
+                        [
+                            format!("{}{}", node_x, strand_x),
+                            format!("{}{}", node_y, strand_y),
+                        ],
+                        Vec::new(),
+                    );
+                }
             }
         }
         line.clear(); // Clear the line buffer for the next read
@@ -36,18 +49,29 @@ pub fn local_to_global(
 
     // Graph B
     // We init the edges collection
-    let file = File::open(graph_b_file)?;
-    let mut reader = BufReader::new(file);
-    let mut edges_b_collection: HashMap<[String; 2],Vec<String>> = HashMap::new();
+    let mut reader: GfaReader = GfaReader::open(graph_b_file)?;
+    let mut edges_b_collection: HashMap<[String; 2], Vec<String>> = HashMap::new();
 
     while reader.read_line(&mut line)? > 0 {
         let columns: Vec<&str> = line.split('\t').collect();
         if let Some(first_char) = line.chars().next() {
             if first_char == 'L' {
                 // In the case of an L-line, we store the two node names and init an empty vec
-                let node_x_name = String::from(columns[1].to_owned()+columns[2]);
-                let node_y_name = String::from(columns[3].to_owned()+columns[4]);
-                edges_b_collection.insert([node_x_name,node_y_name], Vec::new());
+                let node_x_name = columns[1].to_owned() + columns[2];
+                let node_y_name = columns[3].to_owned() + columns[4];
+                edges_b_collection.insert([node_x_name, node_y_name], Vec::new());
+            }
+            if first_char == 'E' && columns.len() > 7 {
+                // In the case of a GFA2 E-line, only dovetail edges are links, read as for spurious breakpoints
+                if let Some(((node_x, strand_x), (node_y, strand_y))) = dovetail(&columns)? {
+                    edges_b_collection.insert(
+                        [
+                            format!("{}{}", node_x, strand_x),
+                            format!("{}{}", node_y, strand_y),
+                        ],
+                        Vec::new(),
+                    );
+                }
             }
         }
         line.clear(); // Clear the line buffer for the next read
@@ -59,50 +83,63 @@ pub fn local_to_global(
     // We fill the according collection with info about breakpoints paths
     while reader.read_line(&mut line)? > 0 {
         let columns: Vec<&str> = line.split('\t').collect();
-        if  let Some(first_char) = line.chars().next() {
+        if let Some(first_char) = line.chars().next() {
             if first_char != '#' {
                 // We skip comment lines
                 let path_name = String::from(columns[0]);
                 let edit_type = String::from(columns[2]);
                 let node_a = String::from(columns[3]);
                 let node_b = String::from(columns[4]);
+                // Strands of the nodes in the path, forward if the edit file does not report them
+                let strand_a = columns.get(7).map_or("+", |s| s.trim());
+                let strand_b = columns.get(8).map_or("+", |s| s.trim());
                 if edit_type == "S" {
-                    let positive_node_a = node_a.clone() + "+";
-                    let negative_node_a = node_a.clone() + "-";
-                    // We need to find all edges that uses the node_a as predecessor in edges_a_collection
-                    for ([x,y], vec) in edges_a_collection.iter_mut() {
-                        if positive_node_a == *x || negative_node_a == *y {
+                    let leaving_node_a = node_a.clone() + strand_a;
+                    let entering_node_a = node_a.clone() + reverse_strand(strand_a);
+                    // We need to find all edges that leaves node_a in the path direction in edges_a_collection
+                    for ([x, y], vec) in edges_a_collection.iter_mut() {
+                        if leaving_node_a == *x || entering_node_a == *y {
                             vec.push(path_name.clone());
                         }
                     }
-                } else if  edit_type == "M" {
-                    let positive_node_b = node_b.clone() + "+";
-                    let negative_node_b = node_b.clone() + "-";
-                    // We need to find all edges that uses the node_b as predecessor in edges_b_collection
-                    for ([x,y], vec) in edges_b_collection.iter_mut() {
-                        if positive_node_b == *x || negative_node_b == *y {
+                } else if edit_type == "M" {
+                    let leaving_node_b = node_b.clone() + strand_b;
+                    let entering_node_b = node_b.clone() + reverse_strand(strand_b);
+                    // We need to find all edges that leaves node_b in the path direction in edges_b_collection
+                    for ([x, y], vec) in edges_b_collection.iter_mut() {
+                        if leaving_node_b == *x || entering_node_b == *y {
                             vec.push(path_name.clone());
                         }
                     }
-                    
                 }
             }
         }
         line.clear(); // Clear the line buffer for the next read
     }
 
-    // We print to stdio individual breakpoints
+    // We print to stdio individual breakpoints, sorted by edge so that the output does not change between runs
     println!("# Graph\tx\ty\tPaths");
-    for ([x,y], vec) in edges_a_collection.iter() {
+    let mut edges_a: Vec<(&[String; 2], &Vec<String>)> = edges_a_collection.iter().collect();
+    edges_a.sort();
+    for ([x, y], vec) in edges_a {
         if !vec.is_empty() {
-            println!("A\t{}\t{}\t{:?}",x,y,vec);
+            println!("A\t{}\t{}\t{:?}", x, y, vec);
         }
     }
-    for ([x,y], vec) in edges_b_collection.iter() {
+    let mut edges_b: Vec<(&[String; 2], &Vec<String>)> = edges_b_collection.iter().collect();
+    edges_b.sort();
+    for ([x, y], vec) in edges_b {
         if !vec.is_empty() {
-            println!("B\t{}\t{}\t{:?}",x,y,vec);
+            println!("B\t{}\t{}\t{:?}", x, y, vec);
         }
     }
     Ok(())
-    
-}
\ No newline at end of file
+}
+
+fn reverse_strand(strand: &str) -> &str {
+    if strand == "-" {
+        "+"
+    } else {
+        "-"
+    }
+}
@@ -1,25 +1,46 @@
-mod annotate_edit_lengths;
-mod compute_distance;
-mod evaluate_spuriousness;
-mod index_gfa_file;
-mod local_to_global;
-
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use rs_pancat_compare::common_coarsening::CoarseningReport;
+use rs_pancat_compare::common_refinement::CommonRefinement;
+use rs_pancat_compare::compute_distance::{DistanceOptions, DistanceReport};
+use rs_pancat_compare::distance_matrix::TreeMethod;
+use rs_pancat_compare::evaluate_spuriousness::{Junction, SpuriousMode};
+use rs_pancat_compare::index_gfa_file::GfaIndex;
+use rs_pancat_compare::normalize_path_names::PathNaming;
+use rs_pancat_compare::select_regions::Regions;
+use rs_pancat_compare::write_report::OutputFormat;
+use rs_pancat_compare::{
+    annotate_edit_lengths, common_coarsening, common_refinement, compute_distance, distance_matrix,
+    evaluate_spuriousness, index_gfa_file, local_to_global, normalize_graph, normalize_path_names,
+    select_regions, store_gfa_index, verify_edits,
+};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Parser, Debug)]
 #[command(
     version = "v0.1.4",
     about = "GFA graph comparison tool",
-    long_about = "Compares pangenome graphs by calculating the segmentation distance between two GFA (Graphical Fragment Assembly) files."
+    long_about = "Compares pangenome graphs by calculating the segmentation distance between two GFA (Graphical Fragment Assembly) files.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Cli {
-    /// The path to the first GFA file
-    file_path_a: String,
-    /// The path to the second GFA file
-    file_path_b: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The path to the first GFA file (plain, gzip or BGZF)
+    #[arg(required = true)]
+    file_path_a: Option<String>,
+    /// The path to the second GFA file (plain, gzip or BGZF)
+    #[arg(required = true)]
+    file_path_b: Option<String>,
     /// Checks for spurious breakpoints in graphs
     #[clap(long = "spurious", short = 's', action)]
     spurious: bool,
+    /// How spurious breakpoints are found: topology (from links between node ends of shared paths) or paths (from consecutive nodes of shared paths, excluding path ends)
+    #[clap(long = "spurious-mode", default_value = "topology")]
+    spurious_mode: SpuriousMode,
+    /// Lists the spurious breakpoints that were not counted as edits in this file (with -s)
+    #[clap(long = "spurious-report", requires = "spurious")]
+    spurious_report: Option<String>,
     /// Enables hard match for path names (case and block sensitive)
     #[clap(long = "hard", short = 'H', action)]
     hard: bool,
@@ -29,10 +50,99 @@ struct Cli {
     /// Output new edit file with global breakpoints
     #[clap(long = "global", short = 'g', action)]
     global: Option<String>,
+    /// Writes the common refinement of the two graphs as GFA instead of edits
+    #[clap(long = "refine", action)]
+    refine: bool,
+    /// Writes the breakpoints shared by the two graphs as BED instead of edits
+    #[clap(long = "shared", action)]
+    shared: bool,
     /// Filter the paths to be annotated
     #[clap(long = "filter", short = 'f', action)]
     filter: Option<String>,
+    /// Characters separating fields of PanSN path names (sample#haplotype#contig)
+    #[clap(long = "delimiter", short = 'd', default_value = "#")]
+    delimiter: String,
+    /// Keeps only the first fields of PanSN path names to pair paths (1: sample, 2: sample and haplotype)
+    #[clap(long = "pansn-fields", short = 'p')]
+    pansn_fields: Option<usize>,
+    /// Number of paths compared at the same time
+    #[clap(long = "threads", short = 't', default_value_t = 1)]
+    threads: usize,
+    /// Only reports edits inside this region, given as PATH:START-END (0-based, end excluded), can be repeated
+    #[clap(long = "region", short = 'r')]
+    region: Vec<String>,
+    /// Only reports edits inside the regions of a BED file (path name, start, end)
+    #[clap(long = "regions")]
+    regions_file: Option<String>,
+    /// Drops edits inside the regions of a BED file (path name, start, end), such as centromeres
+    #[clap(long = "mask")]
+    mask_file: Option<String>,
+    /// Writes splits, merges and edits per window of this size along each path, as bedGraph, instead of edits
+    #[clap(long = "window", short = 'w')]
+    window: Option<u64>,
+    /// Output format: tsv, json (a single document) or jsonl (one record per line)
+    #[clap(long = "format", default_value = "tsv")]
+    format: OutputFormat,
+    /// Two-columns TSV file pairing path names of the first graph with path names of the second graph
+    #[clap(long = "map", short = 'm')]
+    mapping: Option<String>,
+}
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Writes an index file next to each GFA file, used by later comparisons while the graph is unchanged
+    Index {
+        /// The paths to the GFA files to index
+        #[arg(required = true)]
+        file_paths: Vec<String>,
+    },
+    /// Merges linear chains of nodes joined by spurious junctions, and writes the compacted graph as GFA1
+    Normalize {
+        /// The path to the GFA file to normalize (plain, gzip or BGZF)
+        file_path: String,
+    },
+    /// Compares all pairs of graphs, writes the distance matrix and a tree of the graphs in Newick format
+    Matrix {
+        /// The paths to the GFA files to compare (plain, gzip or BGZF)
+        #[arg(required = true, num_args = 2..)]
+        file_paths: Vec<String>,
+        /// Method used to build the tree: upgma or nj (neighbour-joining)
+        #[clap(long = "tree", default_value = "upgma")]
+        tree: TreeMethod,
+        /// Enables hard match for path names (case and block sensitive)
+        #[clap(long = "hard", short = 'H', action)]
+        hard: bool,
+        /// Characters separating fields of PanSN path names (sample#haplotype#contig)
+        #[clap(long = "delimiter", short = 'd', default_value = "#")]
+        delimiter: String,
+        /// Keeps only the first fields of PanSN path names to pair paths (1: sample, 2: sample and haplotype)
+        #[clap(long = "pansn-fields", short = 'p')]
+        pansn_fields: Option<usize>,
+        /// Number of paths compared at the same time
+        #[clap(long = "threads", short = 't', default_value_t = 1)]
+        threads: usize,
+    },
+    /// Checks that the edits of an edit file (TSV output) transform the first graph into the second graph
+    Verify {
+        /// The path to the edit file
+        edit_file: String,
+        /// The path to the first GFA file the edit file was computed from
+        file_path_a: String,
+        /// The path to the second GFA file the edit file was computed from
+        file_path_b: String,
+        /// Enables hard match for path names (case and block sensitive)
+        #[clap(long = "hard", short = 'H', action)]
+        hard: bool,
+        /// Characters separating fields of PanSN path names (sample#haplotype#contig)
+        #[clap(long = "delimiter", short = 'd', default_value = "#")]
+        delimiter: String,
+        /// Keeps only the first fields of PanSN path names to pair paths (1: sample, 2: sample and haplotype)
+        #[clap(long = "pansn-fields", short = 'p')]
+        pansn_fields: Option<usize>,
+        /// Two-columns TSV file pairing path names of the first graph with path names of the second graph
+        #[clap(long = "map", short = 'm')]
+        mapping: Option<String>,
+    },
 }
 
 fn main() {
@@ -46,106 +156,407 @@ fn main() {
     // Get the file path from command line arguments
     let args: Cli = Cli::parse();
 
-    // Parse first graph
-    let (seq_lengths_a, path_descriptors_a, path_lengths_a, path_types_a) =
-        match index_gfa_file::index_gfa(&args.file_path_a, args.hard) {
-            Ok(result) => result,
+    if let Some(Command::Index { file_paths }) = &args.command {
+        for file_path in file_paths {
+            match index_gfa_file::parse_gfa(file_path)
+                .and_then(|index| store_gfa_index::write_index(file_path, &index))
+            {
+                Ok(index_path) => eprintln!("Indexed {} in {}", file_path, index_path),
+                Err(error) => {
+                    eprintln!("Failed to index GFA file {}: {}", file_path, error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        return;
+    }
+    if let Some(Command::Normalize { file_path }) = &args.command {
+        match normalize_graph::normalize(file_path) {
+            Ok(chains) => eprintln!(
+                "Merged {} nodes into {} nodes",
+                chains.chains.iter().map(|chain| chain.len()).sum::<usize>(),
+                chains.chains.len()
+            ),
             Err(error) => {
-                eprintln!("Failed to read GFA file: {}", error);
-                return;
+                eprintln!("Failed to normalize GFA file {}: {}", file_path, error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Matrix {
+        file_paths,
+        tree,
+        hard,
+        delimiter,
+        pansn_fields,
+        threads,
+    }) = &args.command
+    {
+        let naming: PathNaming = PathNaming {
+            hard: *hard,
+            delimiters: delimiter.clone(),
+            pansn_fields: *pansn_fields,
+            mapping: HashMap::new(),
+        };
+        // Each graph is indexed once for all its comparisons
+        let mut indexes: Vec<GfaIndex> = Vec::with_capacity(file_paths.len());
+        for file_path in file_paths {
+            match index_gfa_file::index_gfa(file_path, &naming) {
+                Ok((index, warning)) => {
+                    if let Some(warning) = warning {
+                        eprintln!("Warning: {}", warning);
+                    }
+                    indexes.push(index)
+                }
+                Err(error) => {
+                    eprintln!("Failed to read GFA file {}: {}", file_path, error);
+                    std::process::exit(1);
+                }
             }
+        }
+        // Paths with different lengths in two graphs are left out of all pairs
+        let (paths, excluded): (Vec<String>, Vec<String>) = distance_matrix::common_paths(&indexes);
+        for path_name in excluded.iter() {
+            let lengths: Vec<String> = indexes
+                .iter()
+                .map(|index| index.path_lengths[path_name].to_string())
+                .collect();
+            eprintln!(
+                "Warning: path {} has different lengths in the graphs ({}), it is not compared",
+                path_name,
+                lengths.join(", ")
+            );
+        }
+        if paths.is_empty() {
+            eprintln!("Error: No path found in all graphs.");
+            std::process::exit(1);
+        }
+        let options: DistanceOptions = DistanceOptions {
+            threads: *threads,
+            ..DistanceOptions::default()
+        };
+        let matrix: Vec<Vec<u64>> =
+            match distance_matrix::distance_matrix(file_paths, &indexes, &paths, &options) {
+                Ok(result) => result,
+                Err(error) => {
+                    eprintln!("Failed to compare graphs: {}", error);
+                    std::process::exit(1);
+                }
+            };
+        let names: Vec<String> = distance_matrix::graph_names(file_paths);
+        let newick: String = match tree {
+            TreeMethod::Upgma => distance_matrix::upgma(&names, &matrix),
+            TreeMethod::NeighbourJoining => distance_matrix::neighbour_joining(&names, &matrix),
         };
-    // If path_descriptors_a is empty, the GFA file is not in GFA1.0 format
-    if path_descriptors_a.is_empty() {
+        distance_matrix::write_matrix(
+            &mut std::io::BufWriter::new(std::io::stdout()),
+            &names,
+            &paths,
+            &excluded,
+            &matrix,
+            &newick,
+        )
+        .unwrap();
+        return;
+    }
+    if let Some(Command::Verify {
+        edit_file,
+        file_path_a,
+        file_path_b,
+        hard,
+        delimiter,
+        pansn_fields,
+        mapping,
+    }) = &args.command
+    {
+        let (mapping_a, mapping_b) = match mapping {
+            Some(mapping_file) => match normalize_path_names::read_mapping(mapping_file) {
+                Ok(result) => result,
+                Err(error) => {
+                    eprintln!("Failed to read mapping file {}: {}", mapping_file, error);
+                    std::process::exit(1);
+                }
+            },
+            None => (HashMap::new(), HashMap::new()),
+        };
+        let mut indexes: Vec<GfaIndex> = Vec::with_capacity(2);
+        for (file_path, mapping) in [(file_path_a, mapping_a), (file_path_b, mapping_b)] {
+            let naming: PathNaming = PathNaming {
+                hard: *hard,
+                delimiters: delimiter.clone(),
+                pansn_fields: *pansn_fields,
+                mapping,
+            };
+            match index_gfa_file::index_gfa(file_path, &naming) {
+                Ok((index, warning)) => {
+                    if let Some(warning) = warning {
+                        eprintln!("Warning: {}", warning);
+                    }
+                    indexes.push(index)
+                }
+                Err(error) => {
+                    eprintln!("Failed to read GFA file {}: {}", file_path, error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        match verify_edits::verify(
+            file_path_a,
+            file_path_b,
+            &indexes[0],
+            &indexes[1],
+            edit_file,
+        ) {
+            // The exit status tells if every path is reproduced
+            Ok((results, warnings)) => {
+                for warning in warnings.iter() {
+                    eprintln!("Warning: {}", warning);
+                }
+                if results
+                    .iter()
+                    .any(|(_, _, divergence)| divergence.is_some())
+                {
+                    std::process::exit(1);
+                }
+            }
+            Err(error) => {
+                eprintln!("Failed to verify edit file {}: {}", edit_file, error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let file_path_a: String = args.file_path_a.clone().unwrap();
+    let file_path_b: String = args.file_path_b.clone().unwrap();
+
+    // Rules to pair path names between the two graphs
+    let (mapping_a, mapping_b) = match &args.mapping {
+        Some(mapping_file) => match normalize_path_names::read_mapping(mapping_file) {
+            Ok(result) => result,
+            Err(error) => {
+                eprintln!("Failed to read mapping file {}: {}", mapping_file, error);
+                std::process::exit(1);
+            }
+        },
+        None => (HashMap::new(), HashMap::new()),
+    };
+    let naming_a: PathNaming = PathNaming {
+        hard: args.hard,
+        delimiters: args.delimiter.clone(),
+        pansn_fields: args.pansn_fields,
+        mapping: mapping_a,
+    };
+    let naming_b: PathNaming = PathNaming {
+        hard: args.hard,
+        delimiters: args.delimiter.clone(),
+        pansn_fields: args.pansn_fields,
+        mapping: mapping_b,
+    };
+
+    // Regions where edits are reported, path names are paired as the paths of the first graph
+    let mut regions: Vec<(String, u64, u64)> = Vec::new();
+    if let Some(regions_file) = &args.regions_file {
+        match select_regions::read_bed(regions_file) {
+            Ok(result) => regions.extend(result),
+            Err(error) => {
+                eprintln!("Failed to read BED file {}: {}", regions_file, error);
+                std::process::exit(1);
+            }
+        }
+    }
+    for region in args.region.iter() {
+        match select_regions::parse_region(region) {
+            Ok(result) => regions.push(result),
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+    let regions: Option<Regions> = if args.region.is_empty() && args.regions_file.is_none() {
+        None
+    } else {
+        Some(Regions::new(
+            regions
+                .into_iter()
+                .map(|(path_name, start, end)| (naming_a.normalize(&path_name), start, end))
+                .collect(),
+        ))
+    };
+    // Regions where edits are dropped
+    let mask: Option<Regions> =
+        args.mask_file
+            .as_ref()
+            .map(|mask_file| match select_regions::read_bed(mask_file) {
+                Ok(result) => Regions::new(
+                    result
+                        .into_iter()
+                        .map(|(path_name, start, end)| (naming_a.normalize(&path_name), start, end))
+                        .collect(),
+                ),
+                Err(error) => {
+                    eprintln!("Failed to read BED file {}: {}", mask_file, error);
+                    std::process::exit(1);
+                }
+            });
+
+    // Parse first graph
+    let index_a: GfaIndex = match index_gfa_file::index_gfa(&file_path_a, &naming_a) {
+        Ok((result, warning)) => {
+            if let Some(warning) = warning {
+                eprintln!("Warning: {}", warning);
+            }
+            result
+        }
+        Err(error) => {
+            eprintln!("Failed to read GFA file {}: {}", file_path_a, error);
+            std::process::exit(1);
+        }
+    };
+    // If the index has no path, the GFA file is not in GFA1.0 format
+    if index_a.path_positions.is_empty() {
         eprintln!("Error: No paths found in graph.");
         std::process::exit(1);
     }
 
     // Parse second graph
-    let (seq_lengths_b, path_descriptors_b, path_lengths_b, path_types_b) =
-        match index_gfa_file::index_gfa(&args.file_path_b, args.hard) {
-            Ok(result) => result,
-            Err(error) => {
-                eprintln!("Failed to read GFA file: {}", error);
-                return;
+    let index_b: GfaIndex = match index_gfa_file::index_gfa(&file_path_b, &naming_b) {
+        Ok((result, warning)) => {
+            if let Some(warning) = warning {
+                eprintln!("Warning: {}", warning);
             }
-        };
+            result
+        }
+        Err(error) => {
+            eprintln!("Failed to read GFA file {}: {}", file_path_b, error);
+            std::process::exit(1);
+        }
+    };
 
-    // If path_descriptors_b is empty, the GFA file is not in GFA1.0 format
-    if path_descriptors_b.is_empty() {
+    // If the index has no path, the GFA file is not in GFA1.0 format
+    if index_b.path_positions.is_empty() {
         eprintln!("Error: No paths found in graph.");
         std::process::exit(1);
     }
 
-    let spurious_nodes_a: Vec<String>;
-    let spurious_nodes_b: Vec<String>;
+    let spurious_nodes_a: HashSet<Junction>;
+    let spurious_nodes_b: HashSet<Junction>;
 
     if args.spurious {
+        // Spuriousness is computed on the subgraphs induced by the paths found in both graphs
+        let intersection: Vec<String> = compute_distance::path_intersection(&index_a, &index_b);
         // Check for spurious breakpoints in the first graph
-        spurious_nodes_a = evaluate_spuriousness::spurious_breakpoints(&args.file_path_a).unwrap();
+        spurious_nodes_a = evaluate_spuriousness::subgraph_spurious_breakpoints(
+            &file_path_a,
+            &index_a,
+            &intersection,
+            args.spurious_mode,
+        )
+        .unwrap();
 
         // Check for spurious breakpoints in the second graph
-        spurious_nodes_b = evaluate_spuriousness::spurious_breakpoints(&args.file_path_b).unwrap();
+        spurious_nodes_b = evaluate_spuriousness::subgraph_spurious_breakpoints(
+            &file_path_b,
+            &index_b,
+            &intersection,
+            args.spurious_mode,
+        )
+        .unwrap();
     } else {
         // If the spurious option is not given, do not check for spurious breakpoints
-        // Init empty vectors
-        spurious_nodes_a = Vec::new();
-        spurious_nodes_b = Vec::new();
+        // Init empty sets
+        spurious_nodes_a = HashSet::new();
+        spurious_nodes_b = HashSet::new();
     }
     // If the annotate option is given, annotate the editions with their lengths
     if args.annotate.is_some() {
-        // Create empty vector
-        let intersection: Vec<String>;
-        if args.filter.is_some() {
+        let intersection: Vec<String> = if args.filter.is_some() {
             // Create a vector containing the string as sole element
-            intersection = vec![args.filter.as_deref().unwrap().to_string()];
+            vec![args.filter.as_deref().unwrap().to_string()]
         } else {
             //  Compute path intersection
-            intersection = path_descriptors_a
-                .keys()
-                .filter(|&k| path_descriptors_b.contains_key(k))
-                .map(|s| s.to_string())
-                .collect();
-        }
+            compute_distance::path_intersection(&index_a, &index_b)
+        };
         annotate_edit_lengths::annotate_editions(
-            &args.file_path_a,
-            &args.file_path_b,
+            &file_path_a,
+            &file_path_b,
             args.annotate.as_deref().unwrap(),
-            seq_lengths_a,
-            seq_lengths_b,
-            path_descriptors_a,
-            path_descriptors_b,
-            path_lengths_a,
-            path_lengths_b,
-            path_types_a,
-            path_types_b,
+            &index_a,
+            &index_b,
             intersection,
         )
         .unwrap();
-    } // If the global option is given, reformulate editions to global dist
+    }
+    // If the global option is given, reformulate editions to global dist
     else if args.global.is_some() {
         local_to_global::local_to_global(
-            &args.file_path_a,
-            &args.file_path_b,
+            &file_path_a,
+            &file_path_b,
             args.global.as_deref().unwrap(),
         )
         .unwrap();
-    } // Else, compute the distance between the two graphs
+    }
+    // If the refine option is given, write the common refinement of the two graphs
+    else if args.refine {
+        let refinement: CommonRefinement =
+            common_refinement::refine(&file_path_a, &file_path_b, &index_a, &index_b).unwrap();
+        warn_skipped_paths(&refinement.skipped_paths);
+    }
+    // If the shared option is given, write the common coarsening of the two graphs
+    else if args.shared {
+        let report: CoarseningReport =
+            common_coarsening::shared_breakpoints(&file_path_a, &file_path_b, &index_a, &index_b)
+                .unwrap();
+        warn_skipped_paths(&report.skipped_paths);
+        // The summary goes to standard error, so that standard output only holds BED records
+        common_coarsening::write_summary(&report.counts, &mut std::io::stderr()).unwrap();
+    }
+    // Else, compute the distance between the two graphs
     else {
-        compute_distance::distance(
-            &args.file_path_a,
-            &args.file_path_b,
-            seq_lengths_a,
-            seq_lengths_b,
-            path_descriptors_a,
-            path_descriptors_b,
-            path_lengths_a,
-            path_lengths_b,
-            path_types_a,
-            path_types_b,
+        if let Some(regions) = &regions {
+            let intersection: Vec<String> = compute_distance::path_intersection(&index_a, &index_b);
+            for path_name in regions.path_names() {
+                if !intersection.contains(path_name) {
+                    eprintln!(
+                        "Warning: region on path {} which is not in both graphs",
+                        path_name
+                    );
+                }
+            }
+        }
+        let report: DistanceReport = compute_distance::distance(
+            &file_path_a,
+            &file_path_b,
+            &index_a,
+            &index_b,
             spurious_nodes_a,
             spurious_nodes_b,
+            &DistanceOptions {
+                threads: args.threads,
+                format: args.format,
+                regions,
+                mask,
+                window: args.window,
+                spurious_report: args.spurious_report,
+            },
         )
         .unwrap();
+        // Densities have no room for skipped paths, so they are reported here
+        if args.window.is_some() {
+            warn_skipped_paths(&report.skipped_paths);
+        }
+    }
+}
+
+fn warn_skipped_paths(skipped_paths: &[(String, u64, u64)]) {
+    /*
+    Writes a warning to standard error for each path left out as it has different lengths in the two graphs
+    */
+    for (path_name, length1, length2) in skipped_paths {
+        eprintln!(
+            "Warning: the two paths representing {} have different lengths: {} and {}",
+            path_name, length1, length2
+        );
     }
 }
@@ -0,0 +1,658 @@
+use crate::evaluate_spuriousness::{flip, spurious_breakpoints, Junction};
+use crate::index_gfa_file::{read_sequences, segment_length};
+use crate::read_gfa_file::GfaReader;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+// Builders cut the same sequences in different ways, and some of their breakpoints carry no information:
+// two nodes joined by a spurious junction are always traversed one after the other, so they can be merged.
+// Normalizing a graph merges every linear chain of nodes joined by spurious junctions into a single node (unchop),
+// and rewrites paths and walks so that they spell the same sequences, giving graphs a canonical form before comparison.
+// A junction is kept apart when merging its nodes would cut a path or a walk: a path starting or ending on one of its node ends,
+// a path leaving one of its node ends through another node end, or a link with an overlap or a containment on its nodes.
+// A merged node is named after the first node of its chain; a circular chain is cut at the junction from its last node to its first one.
+// A merged node keeps the tags shared by all nodes of its chain, except the ones that depend on their sequence or offset.
+// Only GFA1 graphs (S, L, P and W lines) are normalized, other lines are written unchanged.
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Chains {
+    // Nodes of each chain, in the order and orientation of the merged node
+    pub chains: Vec<Vec<(String, char)>>,
+    // Length of each chain, the sum of the lengths of its nodes
+    pub lengths: Vec<u64>,
+    // Node names as keys and their chain, rank in the chain and orientation in the chain as values
+    members: HashMap<String, (usize, usize, char)>,
+}
+
+impl Chains {
+    pub fn new(junctions: &HashSet<Junction>, nodes: &[(String, u64)]) -> Chains {
+        /*
+        Follows junctions to build the chains of nodes to merge
+
+        Arguments:
+        - junctions: the junctions between nodes to merge, in both directions of traversal
+        - nodes: the names and lengths of the nodes, chains are built in this order so that the result does not change between runs
+
+        Returns:
+        - the chains of at least two nodes
+        */
+        // Node ends as keys and the node, with its orientation, entered by leaving them as values
+        let next: HashMap<(&str, char), (&str, char)> = junctions
+            .iter()
+            .map(|(node, strand, next, next_strand)| {
+                ((node.as_str(), *strand), (next.as_str(), *next_strand))
+            })
+            .collect();
+        let lengths: HashMap<&str, u64> = nodes
+            .iter()
+            .map(|(node, length)| (node.as_str(), *length))
+            .collect();
+        let mut chains: Chains = Chains {
+            chains: Vec::new(),
+            lengths: Vec::new(),
+            members: HashMap::new(),
+        };
+        for (node, _) in nodes {
+            if chains.members.contains_key(node) {
+                continue;
+            }
+            // We go back to the first node of the chain, or around a circular chain
+            let mut first: (&str, char) = (node.as_str(), '+');
+            while let Some(&(previous, strand)) = next.get(&(first.0, flip(first.1))) {
+                if previous == node {
+                    break;
+                }
+                first = (previous, flip(strand));
+            }
+            // Then we walk the chain forward
+            let mut chain: Vec<(String, char)> = vec![(first.0.to_string(), first.1)];
+            let mut current: (&str, char) = first;
+            while let Some(&following) = next.get(&current) {
+                if following.0 == first.0 {
+                    break;
+                }
+                chain.push((following.0.to_string(), following.1));
+                current = following;
+            }
+            // A chain read on its reverse strand starts forward if it ends in reverse
+            if chain[0].1 == '-' && chain[chain.len() - 1].1 == '-' {
+                chain = chain
+                    .into_iter()
+                    .rev()
+                    .map(|(member, strand)| (member, flip(strand)))
+                    .collect();
+            }
+            if chain.len() > 1 {
+                for (rank, (member, strand)) in chain.iter().enumerate() {
+                    chains
+                        .members
+                        .insert(member.clone(), (chains.chains.len(), rank, *strand));
+                }
+                chains.lengths.push(
+                    chain
+                        .iter()
+                        .map(|(member, _)| lengths.get(member.as_str()).copied().unwrap_or(0))
+                        .sum(),
+                );
+                chains.chains.push(chain);
+            }
+        }
+        chains
+    }
+
+    pub fn merged_node(&self, node: &str, strand: char, leaving: bool) -> Option<(String, char)> {
+        /*
+        Gives the node end of the normalized graph matching a node end of the graph
+
+        Arguments:
+        - node: the name of the node
+        - strand: the orientation the node is traversed in
+        - leaving: true if the node is left by this traversal, false if it is entered
+
+        Returns:
+        - the node and its orientation in the normalized graph, None if the node end is inside a merged node
+        */
+        match self.members.get(node) {
+            None => Some((node.to_string(), strand)),
+            Some(&(chain, rank, chain_strand)) => {
+                let forward: bool = strand == chain_strand;
+                let last: usize = self.chains[chain].len() - 1;
+                // A chain is left forward by its last node, and entered forward by its first node
+                let boundary: usize = if forward == leaving { last } else { 0 };
+                if rank == boundary {
+                    Some((
+                        self.chains[chain][0].0.clone(),
+                        if forward { '+' } else { '-' },
+                    ))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    pub fn rewrite_path(&self, steps: &[(String, char)]) -> Vec<(String, char)> {
+        /*
+        Rewrites the steps of a path on the normalized graph: each traversal of a chain becomes a step on its merged node
+        */
+        steps
+            .iter()
+            .filter_map(|(node, strand)| self.merged_node(node, *strand, false))
+            .collect()
+    }
+}
+
+pub fn find_chains(file_path: &str) -> io::Result<Chains> {
+    /*
+    Computes the chains of nodes joined by spurious junctions that can be merged without changing paths and walks
+
+    Arguments:
+    - file_path: the path to the GFA file
+
+    Returns:
+    - the chains of nodes to merge
+    */
+    let mut junctions: HashSet<Junction> = spurious_breakpoints(file_path)?;
+    let next: HashMap<(String, char), (String, char)> = junctions
+        .iter()
+        .map(|(node, strand, next, next_strand)| {
+            ((node.clone(), *strand), (next.clone(), *next_strand))
+        })
+        .collect();
+    // Node ends that cannot be inside a merged node
+    let mut blocked: HashSet<(String, char)> = HashSet::new();
+    let mut nodes: Vec<(String, u64)> = Vec::new();
+    let mut reader: GfaReader = GfaReader::open(file_path)?;
+    let mut line: String = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let columns: Vec<&str> = line.trim_end_matches(['\n', '\r']).split('\t').collect();
+        match columns[0] {
+            "H" if columns.iter().any(|tag| tag.starts_with("VN:Z:2")) => {
+                return Err(gfa2_error());
+            }
+            "E" | "F" | "G" | "O" | "U" => return Err(gfa2_error()),
+            "S" if columns.len() > 1 => {
+                nodes.push((columns[1].to_string(), segment_length(&columns, false)?))
+            }
+            "L" if columns.len() > 5 && !matches!(columns[5], "*" | "0M") => {
+                // Sequences of nodes joined by an overlap can't be concatenated
+                let (from, to) = link_ends(&columns)?;
+                blocked.insert(from);
+                blocked.insert(to);
+            }
+            "C" | "J" if columns.len() > 3 => {
+                // Containments and jumps refer to the whole nodes
+                for node in [columns[1], columns[3]] {
+                    blocked.insert((node.to_string(), '+'));
+                    blocked.insert((node.to_string(), '-'));
+                }
+            }
+            "P" | "W" => {
+                let steps: Vec<(String, char)> = path_steps(&columns)?;
+                // The path enters its first node by its start if forward, and leaves its last node by its end if forward
+                if let (Some((first, first_strand)), Some((last, last_strand))) =
+                    (steps.first(), steps.last())
+                {
+                    blocked.insert((first.clone(), flip(*first_strand)));
+                    blocked.insert((last.clone(), *last_strand));
+                }
+                // The path must follow the junctions of the node ends it goes through
+                for pair in steps.windows(2) {
+                    let (from, to): (&(String, char), &(String, char)) = (&pair[0], &pair[1]);
+                    let entered: (String, char) = (to.0.clone(), flip(to.1));
+                    let backward: (String, char) = (from.0.clone(), flip(from.1));
+                    if next.get(from).is_some_and(|next| next != to)
+                        || next.get(&entered).is_some_and(|next| *next != backward)
+                    {
+                        blocked.insert(from.clone());
+                        blocked.insert(entered);
+                    }
+                }
+            }
+            _ => {}
+        }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    junctions.retain(|(node, strand, next, next_strand)| {
+        !blocked.contains(&(node.clone(), *strand))
+            && !blocked.contains(&(next.clone(), flip(*next_strand)))
+    });
+    Ok(Chains::new(&junctions, &nodes))
+}
+
+pub fn normalize(file_path: &str) -> io::Result<Chains> {
+    /*
+    Merges the linear chains of nodes joined by spurious junctions
+
+    Arguments:
+    - file_path: the path to the GFA file
+
+    Output:
+    - Writes to standard output the normalized graph as GFA1: merged nodes replace their chains, links inside chains are removed,
+      and paths and walks go through merged nodes
+
+    Returns:
+    - the chains of nodes that were merged
+    */
+    let mut writer: io::BufWriter<io::Stdout> = io::BufWriter::new(io::stdout());
+    let chains: Chains = write_normalized(file_path, &mut writer)?;
+    writer.flush()?;
+    Ok(chains)
+}
+
+pub fn write_normalized<W: Write>(file_path: &str, writer: &mut W) -> io::Result<Chains> {
+    /*
+    Writes the normalized graph, see normalize
+
+    Arguments:
+    - file_path: the path to the GFA file
+    - writer: where the normalized graph is written
+
+    Returns:
+    - the chains of nodes that were merged
+    */
+    let chains: Chains = find_chains(file_path)?;
+    let members: HashSet<String> = chains.members.keys().cloned().collect();
+    let sequences: HashMap<String, String> = read_sequences(file_path, &members)?;
+    let tags: HashMap<String, Vec<String>> = read_tags(file_path, &members)?;
+
+    let mut reader: GfaReader = GfaReader::open(file_path)?;
+    let mut line: String = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let content: &str = line.trim_end_matches(['\n', '\r']);
+        let columns: Vec<&str> = content.split('\t').collect();
+        match columns[0] {
+            "S" if columns.len() > 1 && members.contains(columns[1]) => {
+                // A merged node replaces the first node of its chain, the other nodes are removed
+                let (chain, rank, _) = chains.members[columns[1]];
+                if rank == 0 {
+                    let mut fields: Vec<String> = vec![
+                        "S".to_string(),
+                        columns[1].to_string(),
+                        merged_sequence(&chains.chains[chain], &sequences),
+                        format!("LN:i:{}", chains.lengths[chain]),
+                    ];
+                    fields.extend(merged_tags(&chains.chains[chain], &tags));
+                    writeln!(writer, "{}", fields.join("\t"))?;
+                }
+            }
+            "L" => {
+                if columns.len() < 5 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid link '{}'", content),
+                    ));
+                }
+                let (from, to) = (
+                    chains.merged_node(columns[1], link_orientation(columns[2])?, true),
+                    chains.merged_node(columns[3], link_orientation(columns[4])?, false),
+                );
+                // Links inside chains disappear with them
+                if let (Some((from, from_strand)), Some((to, to_strand))) = (from, to) {
+                    let mut fields: Vec<String> = vec![
+                        "L".to_string(),
+                        from,
+                        from_strand.to_string(),
+                        to,
+                        to_strand.to_string(),
+                    ];
+                    fields.extend(columns[5..].iter().map(|s| s.to_string()));
+                    writeln!(writer, "{}", fields.join("\t"))?;
+                }
+            }
+            "P" | "W" => {
+                let original: Vec<(String, char)> = path_steps(&columns)?;
+                // Each kept step, with its rank in the original path
+                let kept: Vec<(usize, (String, char))> = original
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(rank, (node, strand))| {
+                        chains
+                            .merged_node(node, *strand, false)
+                            .map(|step| (rank, step))
+                    })
+                    .collect();
+                let steps: Vec<&(String, char)> = kept.iter().map(|(_, step)| step).collect();
+                if columns[0] == "P" {
+                    let mut fields: Vec<String> = columns.iter().map(|s| s.to_string()).collect();
+                    fields[2] = steps
+                        .iter()
+                        .map(|(node, strand)| format!("{}{}", node, strand))
+                        .collect::<Vec<String>>()
+                        .join(",");
+                    if fields.len() > 3 && fields[3] != "*" {
+                        fields[3] = rewrite_overlaps(columns[3], original.len(), &kept);
+                    }
+                    writeln!(writer, "{}", fields.join("\t"))?;
+                } else {
+                    let mut fields: Vec<String> = columns.iter().map(|s| s.to_string()).collect();
+                    fields[6] = steps
+                        .iter()
+                        .map(|(node, strand)| {
+                            format!("{}{}", if *strand == '+' { '>' } else { '<' }, node)
+                        })
+                        .collect();
+                    writeln!(writer, "{}", fields.join("\t"))?;
+                }
+            }
+            _ => writeln!(writer, "{}", content)?,
+        }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    Ok(chains)
+}
+
+fn rewrite_overlaps(overlaps: &str, length: usize, kept: &[(usize, (String, char))]) -> String {
+    /*
+    Rewrites the overlaps of a P-line on the normalized graph
+    Overlaps inside merged nodes are removed, and a kept step is joined to the next one by the overlap of the step before it,
+    the last node of a merged node or the same step if nothing was merged
+
+    Arguments:
+    - overlaps: the overlaps column of the P-line
+    - length: the number of steps of the original path
+    - kept: the kept steps, with their rank in the original path
+
+    Returns:
+    - the overlaps column of the normalized P-line, '*' if overlaps do not match the steps
+    */
+    let overlaps: Vec<&str> = overlaps.split(',').collect();
+    if overlaps.len() + 1 != length || kept.len() < 2 {
+        return "*".to_string();
+    }
+    kept.windows(2)
+        .map(|pair| overlaps[pair[1].0 - 1])
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+fn merged_sequence(chain: &[(String, char)], sequences: &HashMap<String, String>) -> String {
+    /*
+    Concatenates the sequences of the nodes of a chain, '*' if one of them is omitted
+    */
+    let mut sequence: String = String::new();
+    for (node, strand) in chain.iter() {
+        match sequences.get(node).map(String::as_str) {
+            Some("*") | None => return "*".to_string(),
+            Some(node_sequence) if *strand == '+' => sequence.push_str(node_sequence),
+            Some(node_sequence) => sequence.push_str(&reverse_complement(node_sequence)),
+        }
+    }
+    sequence
+}
+
+fn read_tags(
+    file_path: &str,
+    node_names: &HashSet<String>,
+) -> io::Result<HashMap<String, Vec<String>>> {
+    /*
+    Reads the tags of some S-lines, the columns after the sequence
+    */
+    let mut reader: GfaReader = GfaReader::open(file_path)?;
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    let mut line: String = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let columns: Vec<&str> = line.trim_end_matches(['\n', '\r']).split('\t').collect();
+        if columns[0] == "S" && columns.len() > 1 && node_names.contains(columns[1]) {
+            tags.insert(
+                columns[1].to_string(),
+                columns.iter().skip(3).map(|tag| tag.to_string()).collect(),
+            );
+        }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    Ok(tags)
+}
+
+fn merged_tags(chain: &[(String, char)], tags: &HashMap<String, Vec<String>>) -> Vec<String> {
+    /*
+    Gives the tags of a merged node: the tags all nodes of its chain hold with the same value,
+    except the ones describing their sequence (LN, SH, UR), read counts (RC, FC, KC) or offset (SO), which do not hold anymore
+    */
+    let no_tags: Vec<String> = Vec::new();
+    let node_tags = |node: &str| tags.get(node).unwrap_or(&no_tags);
+    node_tags(&chain[0].0)
+        .iter()
+        .filter(|tag| {
+            !matches!(
+                tag.get(..3),
+                Some("LN:" | "SH:" | "UR:" | "RC:" | "FC:" | "KC:" | "SO:")
+            )
+        })
+        .filter(|tag| chain.iter().all(|(node, _)| node_tags(node).contains(tag)))
+        .cloned()
+        .collect()
+}
+
+fn path_steps(columns: &[&str]) -> io::Result<Vec<(String, char)>> {
+    /*
+    Reads the steps of a P-line (12+,13-) or of a W-line (>12<13)
+    */
+    let invalid = |description: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid path description '{}'", description),
+        )
+    };
+    if columns[0] == "W" {
+        let walk: &str = columns.get(6).ok_or_else(|| invalid(columns[0]))?;
+        let mut steps: Vec<(String, char)> = Vec::new();
+        for character in walk.chars() {
+            match character {
+                '>' => steps.push((String::new(), '+')),
+                '<' => steps.push((String::new(), '-')),
+                _ => match steps.last_mut() {
+                    Some((node, _)) => node.push(character),
+                    None => return Err(invalid(walk)),
+                },
+            }
+        }
+        Ok(steps)
+    } else {
+        let path: &str = columns.get(2).ok_or_else(|| invalid(columns[0]))?;
+        path.split(',')
+            .filter(|step| !step.is_empty())
+            .map(|step| match step.char_indices().last() {
+                Some((index, strand)) if strand == '+' || strand == '-' => {
+                    Ok((step[..index].to_string(), strand))
+                }
+                _ => Err(invalid(path)),
+            })
+            .collect()
+    }
+}
+
+fn link_ends(columns: &[&str]) -> io::Result<((String, char), (String, char))> {
+    /*
+    Gives the two node ends joined by an L-line: the end the path leaves and the end the next node is entered by
+    */
+    Ok((
+        (columns[1].to_string(), link_orientation(columns[2])?),
+        (columns[3].to_string(), flip(link_orientation(columns[4])?)),
+    ))
+}
+
+fn link_orientation(column: &str) -> io::Result<char> {
+    match column {
+        "+" => Ok('+'),
+        "-" => Ok('-'),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid link orientation '{}'", column),
+        )),
+    }
+}
+
+fn gfa2_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "only GFA1 graphs can be normalized",
+    )
+}
+
+pub fn reverse_complement(sequence: &str) -> String {
+    /*
+    Returns the reverse complement of a sequence, keeping case and IUPAC codes
+    */
+    sequence
+        .chars()
+        .rev()
+        .map(|base| match base {
+            'A' => 'T',
+            'T' | 'U' => 'A',
+            'C' => 'G',
+            'G' => 'C',
+            'R' => 'Y',
+            'Y' => 'R',
+            'K' => 'M',
+            'M' => 'K',
+            'B' => 'V',
+            'V' => 'B',
+            'D' => 'H',
+            'H' => 'D',
+            'a' => 't',
+            't' | 'u' => 'a',
+            'c' => 'g',
+            'g' => 'c',
+            'r' => 'y',
+            'y' => 'r',
+            'k' => 'm',
+            'm' => 'k',
+            'b' => 'v',
+            'v' => 'b',
+            'd' => 'h',
+            'h' => 'd',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_chains() {
+        // 1, 2 (reverse) and 3 form a chain, 3 then goes to 4 or back to 1
+        let gfa_path = std::env::temp_dir().join(format!("{}_normalize.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        std::fs::write(
+            gfa_path,
+            "H\tVN:Z:1.0\nS\t1\tAC\nS\t2\tGT\nS\t3\tA\nS\t4\tC\nL\t1\t+\t2\t-\t0M\nL\t2\t-\t3\t+\t0M\nL\t3\t+\t4\t+\t0M\nL\t3\t+\t1\t+\t0M\nP\tx\t1+,2-,3+,4+\t*\nP\ty\t4-,3-,2+,1-\t*\n",
+        )
+        .unwrap();
+        let chains: Chains = find_chains(gfa_path).unwrap();
+        assert_eq!(
+            chains.chains,
+            vec![vec![
+                ("1".to_string(), '+'),
+                ("2".to_string(), '-'),
+                ("3".to_string(), '+')
+            ]]
+        );
+        assert_eq!(chains.lengths, vec![5]);
+        let sequences: HashMap<String, String> = read_sequences(
+            gfa_path,
+            &HashSet::from(["1".to_string(), "2".to_string(), "3".to_string()]),
+        )
+        .unwrap();
+        assert_eq!(merged_sequence(&chains.chains[0], &sequences), "ACACA");
+
+        let step = |node: &str, strand: char| (node.to_string(), strand);
+        assert_eq!(
+            chains.rewrite_path(&[
+                step("1", '+'),
+                step("2", '-'),
+                step("3", '+'),
+                step("4", '+')
+            ]),
+            vec![step("1", '+'), step("4", '+')]
+        );
+        assert_eq!(
+            chains.rewrite_path(&[
+                step("4", '-'),
+                step("3", '-'),
+                step("2", '+'),
+                step("1", '-')
+            ]),
+            vec![step("4", '-'), step("1", '-')]
+        );
+        // The link from the end of 3 to the start of 1 goes around the merged node
+        assert_eq!(chains.merged_node("3", '+', true), Some(step("1", '+')));
+        assert_eq!(chains.merged_node("2", '-', true), None);
+
+        // A path starting on 2 keeps it apart from 1, the chain is found from 2 and read from 3 in reverse
+        std::fs::write(
+            gfa_path,
+            "S\t1\tAC\nS\t2\tGT\nS\t3\tA\nL\t1\t+\t2\t-\t0M\nL\t2\t-\t3\t+\t0M\nP\tx\t1+,2-,3+\t*\nP\ty\t2-,3+\t*\n",
+        )
+        .unwrap();
+        assert_eq!(
+            find_chains(gfa_path).unwrap().chains,
+            vec![vec![("3".to_string(), '-'), ("2".to_string(), '+')]]
+        );
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+
+    #[test]
+    fn test_write_normalized() {
+        // 1, 2 (reverse) and 3 form a chain, y ends on 4, and 4 and 5 are joined by an overlap
+        let gfa_path =
+            std::env::temp_dir().join(format!("{}_normalize_write.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        let graph: &str = "H\tVN:Z:1.0\nS\t1\tAC\tSN:Z:c\tRC:i:5\nS\t2\tGT\tLN:i:2\tSN:Z:c\tRC:i:5\nS\t3\tA\tSN:Z:c\tRC:i:5\txx:Z:a\nS\t4\tCCA\nS\t5\tCAG\nL\t1\t+\t2\t-\t0M\nL\t2\t-\t3\t+\nL\t3\t+\t4\t+\t0M\nL\t4\t+\t5\t+\t2M\nP\tx\t1+,2-,3+,4+,5+\t0M,0M,0M,2M\nP\ty\t5-,4-\t2M\nW\ts\t1\tchr\t0\t11\t>1<2>3>4>5\n";
+        std::fs::write(gfa_path, graph).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        let chains: Chains = write_normalized(gfa_path, &mut output).unwrap();
+        assert_eq!(
+            chains.chains,
+            vec![vec![
+                ("1".to_string(), '+'),
+                ("2".to_string(), '-'),
+                ("3".to_string(), '+')
+            ]]
+        );
+        let output: String = String::from_utf8(output).unwrap();
+
+        // Sequences spelled by each P-line and W-line
+        let spell = |graph: &str| -> Vec<String> {
+            let lines: Vec<Vec<&str>> = graph.lines().map(|l| l.split('\t').collect()).collect();
+            let sequences: HashMap<&str, &str> = lines
+                .iter()
+                .filter(|columns| columns[0] == "S")
+                .map(|columns| (columns[1], columns[2]))
+                .collect();
+            lines
+                .iter()
+                .filter(|columns| matches!(columns[0], "P" | "W"))
+                .map(|columns| {
+                    path_steps(columns)
+                        .unwrap()
+                        .iter()
+                        .map(|(node, strand)| match strand {
+                            '+' => sequences[node.as_str()].to_string(),
+                            _ => reverse_complement(sequences[node.as_str()]),
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        assert_eq!(spell(&output), spell(graph));
+        // The overlap keeps 4 and 5 apart, and overlaps are kept outside of merged nodes
+        assert!(output.contains("P\tx\t1+,4+,5+\t0M,2M\n"));
+        assert!(output.contains("P\ty\t5-,4-\t2M\n"));
+        assert!(output.contains("W\ts\t1\tchr\t0\t11\t>1>4>5\n"));
+        assert!(output.contains("L\t4\t+\t5\t+\t2M\n"));
+        // The merged node keeps the tags shared by its nodes, and the link without overlap inside the chain is removed
+        assert!(output.contains("S\t1\tACACA\tLN:i:5\tSN:Z:c\n"));
+        assert!(!output.contains("L\t2\t"));
+        // A link without its nodes is rejected
+        std::fs::write(gfa_path, "S\t1\tAC\nL\t1\t+\n").unwrap();
+        assert!(write_normalized(gfa_path, &mut Vec::new()).is_err());
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+
+    #[test]
+    fn test_reverse_complement() {
+        assert_eq!(reverse_complement("ACGTNacgtRY"), "RYacgtNACGT");
+    }
+}
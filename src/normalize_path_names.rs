@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+// Paths of the two graphs are paired by name: each path name is turned into a key, and paths sharing a key are compared.
+// Names can be given as-is (hard match), or normalised:
+// - PanSN names (sample#haplotype#contig) can be split on any set of delimiters and truncated to their first fields,
+//   so that a path named HG002.1 can be paired with a walk named HG002#1#chr1
+// - names are capitalized and trailing '#0' is removed, unless hard match is required
+// A mapping file can also pair explicitly any path of the first graph with any path of the second graph:
+// both paths get the key of the path of the first graph, normalised with the same rules as other paths.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathNaming {
+    // Names are kept as-is if true
+    pub hard: bool,
+    // Characters separating PanSN fields
+    pub delimiters: String,
+    // Number of PanSN fields to keep, all of them if None
+    pub pansn_fields: Option<usize>,
+    // Raw path names as keys and the names to normalise instead as values
+    pub mapping: HashMap<String, String>,
+}
+
+impl PathNaming {
+    pub fn normalize(&self, path_name: &str) -> String {
+        /*
+        Computes the key used to pair a path with paths of the other graph
+
+        Arguments:
+        - path_name: the name of the path (P-line name, or sample#haplotype#contig for W-lines)
+
+        Returns:
+        - the key of the path
+        */
+        // A mapped path is named as its paired path of the first graph
+        let path_name: &str = self
+            .mapping
+            .get(path_name)
+            .map_or(path_name, String::as_str);
+        let mut key: String = match self.pansn_fields {
+            Some(fields) => path_name
+                .split(|c: char| self.delimiters.contains(c))
+                .take(fields)
+                .collect::<Vec<&str>>()
+                .join("#"),
+            None => path_name.to_string(),
+        };
+        if !self.hard {
+            // Capitalize path name and remove trailing '#0'
+            key = key.to_ascii_uppercase().trim_end_matches("#0").to_string();
+        }
+        key
+    }
+}
+
+pub fn read_mapping(
+    mapping_file: &str,
+) -> io::Result<(HashMap<String, String>, HashMap<String, String>)> {
+    /*
+    Reads a two-columns mapping file, pairing a path of the first graph with a path of the second graph
+    Lines starting with '#' are comments
+
+    Arguments:
+    - mapping_file: the path to the TSV mapping file (path name in graph A, path name in graph B)
+
+    Returns:
+    - mapping_a: a HashMap with the path names of the first graph as keys and the pairing keys as values
+    - mapping_b: a HashMap with the path names of the second graph as keys and the pairing keys as values
+    - an error if a path name is given twice in the same column
+    */
+    let mut reader: BufReader<File> = BufReader::new(File::open(mapping_file)?);
+    let mut mapping_a: HashMap<String, String> = HashMap::new();
+    let mut mapping_b: HashMap<String, String> = HashMap::new();
+
+    let mut line: String = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let content: &str = line.trim_end_matches(['\n', '\r']);
+        if !content.is_empty() && !content.starts_with('#') {
+            let columns: Vec<&str> = content.split('\t').collect();
+            if columns.len() < 2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("mapping line has less than two columns: {}", content),
+                ));
+            }
+            // Paths are paired under the name they have in the first graph
+            if mapping_a.contains_key(columns[0]) || mapping_b.contains_key(columns[1]) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("path mapped twice in mapping line: {}", content),
+                ));
+            }
+            mapping_a.insert(columns[0].to_string(), columns[0].to_string());
+            mapping_b.insert(columns[1].to_string(), columns[0].to_string());
+        }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    Ok((mapping_a, mapping_b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naming(hard: bool, delimiters: &str, pansn_fields: Option<usize>) -> PathNaming {
+        PathNaming {
+            hard,
+            delimiters: delimiters.to_string(),
+            pansn_fields,
+            mapping: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_default() {
+        let naming: PathNaming = naming(false, "#", None);
+        assert_eq!(naming.normalize("casbjh01#0"), "CASBJH01");
+        assert_eq!(naming.normalize("HG002#1#chr1"), "HG002#1#CHR1");
+    }
+
+    #[test]
+    fn test_normalize_hard() {
+        let naming: PathNaming = naming(true, "#", None);
+        assert_eq!(naming.normalize("casbjh01#0"), "casbjh01#0");
+    }
+
+    #[test]
+    fn test_normalize_pansn() {
+        let naming: PathNaming = naming(false, "#.", Some(2));
+        assert_eq!(naming.normalize("HG002.1"), "HG002#1");
+        assert_eq!(naming.normalize("HG002#1#chr1"), "HG002#1");
+        assert_eq!(naming.normalize("HG002"), "HG002");
+    }
+
+    #[test]
+    fn test_normalize_mapping() {
+        let mut naming: PathNaming = naming(false, "#", None);
+        naming
+            .mapping
+            .insert("chm13#chr1".to_string(), "grch38#chr1".to_string());
+        // Mapped names follow the same rules as other names
+        assert_eq!(naming.normalize("chm13#chr1"), "GRCH38#CHR1");
+        assert_eq!(naming.normalize("chm13#chr2"), "CHM13#CHR2");
+    }
+
+    #[test]
+    fn test_read_mapping_duplicates() {
+        let mapping_path = std::env::temp_dir().join(format!("{}_mapping.tsv", std::process::id()));
+        let mapping_path: &str = mapping_path.to_str().unwrap();
+        std::fs::write(mapping_path, "# A\tB\nx\ty\nz\ty\n").unwrap();
+        assert!(read_mapping(mapping_path).is_err());
+        std::fs::write(mapping_path, "x\ty\nz\tw\n").unwrap();
+        let (mapping_a, mapping_b) = read_mapping(mapping_path).unwrap();
+        assert_eq!(mapping_a["z"], "z");
+        assert_eq!(mapping_b["w"], "z");
+        std::fs::remove_file(mapping_path).unwrap();
+    }
+}
@@ -0,0 +1,758 @@
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use flate2::Crc;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+// GFA files can be given as plain text, gzip (.gfa.gz) or BGZF (bgzip) files.
+// Every reader exposes a position (tell) that can later be given back to seek:
+// - plain files: the byte offset in the file
+// - gzip files: the byte offset in the uncompressed stream (seeking backwards restarts decompression)
+// - BGZF files: the virtual offset, (compressed block offset << 16) | offset inside the uncompressed block
+// Indexes store those positions, so a path can be read again without decompressing the whole file.
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_HEADER_LENGTH: usize = 12;
+const GZIP_FOOTER_LENGTH: usize = 8;
+
+pub enum GfaReader {
+    Plain(PlainReader),
+    Gzip(GzipReader),
+    Bgzf(BgzfReader),
+}
+
+impl GfaReader {
+    pub fn open(file_path: &str) -> io::Result<GfaReader> {
+        /*
+        Opens a GFA file, detecting if it is plain text, gzip or BGZF compressed
+
+        Arguments:
+        - file_path: a string with the path to the GFA file
+
+        Returns:
+        - a reader positioned at the start of the (uncompressed) GFA content
+        */
+        let mut file: File = File::open(file_path)?;
+        let mut header: Vec<u8> = Vec::new();
+        (&mut file)
+            .take(GZIP_HEADER_LENGTH as u64 + u16::MAX as u64)
+            .read_to_end(&mut header)?;
+        file.seek(SeekFrom::Start(0))?;
+
+        if header.len() < 2 || header[0..2] != GZIP_MAGIC {
+            Ok(GfaReader::Plain(PlainReader::new(file)))
+        } else if bgzf_block_size(&header).is_some() {
+            Ok(GfaReader::Bgzf(BgzfReader::new(file)))
+        } else {
+            Ok(GfaReader::Gzip(GzipReader::new(file_path, file)))
+        }
+    }
+
+    pub fn tell(&mut self) -> io::Result<u64> {
+        /*
+        Returns the current position of the reader, to be used later with seek
+        */
+        match self {
+            GfaReader::Plain(reader) => Ok(reader.position),
+            GfaReader::Gzip(reader) => Ok(reader.position),
+            GfaReader::Bgzf(reader) => Ok(reader.virtual_offset()),
+        }
+    }
+
+    pub fn seek(&mut self, position: u64) -> io::Result<()> {
+        /*
+        Moves the reader to a position previously returned by tell
+
+        Arguments:
+        - position: a position given by tell on a reader of the same file
+        */
+        match self {
+            GfaReader::Plain(reader) => reader.seek(position),
+            GfaReader::Gzip(reader) => reader.seek(position),
+            GfaReader::Bgzf(reader) => reader.seek(position),
+        }
+    }
+
+    pub fn seek_path(&mut self, position: u64, path_type: char) -> io::Result<()> {
+        /*
+        Moves the reader to the first node of a path, given the position of its line
+
+        Arguments:
+        - position: the position of the start of the line, as returned by tell
+        - path_type: the type of the path line (P or W for GFA1, O or U groups for GFA2)
+        */
+        self.seek(position)?;
+        // Fields before the path description: P/O/U, name / W, sample, haplotype, contig, start, end
+        let mut fields_to_skip: usize = if path_type == 'W' { 6 } else { 2 };
+        let mut buffer: [u8; 1] = [0; 1];
+        while fields_to_skip > 0 {
+            if self.read(&mut buffer)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "path line ended before its description",
+                ));
+            }
+            if buffer[0] == b'\t' {
+                fields_to_skip -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_next_step(&mut self, path_type: char) -> io::Result<Option<(String, char)>> {
+        /*
+        Reads the next step of a path, the reader being positioned by seek_path or a previous call
+        P-lines steps are written as node followed by orientation (12+,13-)
+        W-lines steps are written as orientation followed by node (>12<13)
+        O-groups steps are written as node followed by orientation, separated by spaces (12+ 13-)
+        U-groups steps are written as nodes without orientation, separated by spaces (12 13)
+
+        Arguments:
+        - path_type: the type of the path line (P, W, O or U)
+
+        Returns:
+        - the node name and its orientation ('+' or '-'), or None at the end of the path
+        */
+        let mut node: String = String::new();
+        let orientation: char;
+        if path_type == 'W' {
+            orientation = match self.next_path_byte()? {
+                Some(b'>') => '+',
+                Some(b'<') => '-',
+                Some(byte) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected character '{}' in walk", byte as char),
+                    ))
+                }
+                None => return Ok(None),
+            };
+            // The node name goes on until the orientation of the next step
+            while let Some(byte) = self.peek_byte()? {
+                if byte == b'>' || byte == b'<' || is_path_end(byte) {
+                    break;
+                }
+                node.push(byte as char);
+                self.consume(1);
+            }
+            if node.is_empty() {
+                return Err(truncated_step(&node));
+            }
+        } else if path_type == 'U' {
+            // Unordered groups have no orientation, we consider members in forward orientation
+            orientation = '+';
+            while let Some(byte) = self.next_path_byte()? {
+                if byte == b' ' {
+                    break;
+                }
+                node.push(byte as char);
+            }
+            if node.is_empty() {
+                return Ok(None);
+            }
+        } else {
+            loop {
+                match self.next_path_byte()? {
+                    Some(b'+') => {
+                        orientation = '+';
+                        break;
+                    }
+                    Some(b'-') => {
+                        orientation = '-';
+                        break;
+                    }
+                    Some(byte) => node.push(byte as char),
+                    None if node.is_empty() => return Ok(None),
+                    None => return Err(truncated_step(&node)),
+                }
+            }
+            // Steps are separated by commas in P-lines and by spaces in O-groups
+            if matches!(self.peek_byte()?, Some(b',') | Some(b' ')) {
+                self.consume(1);
+            }
+        }
+        Ok(Some((node, orientation)))
+    }
+
+    pub fn read_next_segment(
+        &mut self,
+        path_type: char,
+        seq_lengths: &HashMap<String, u64>,
+    ) -> io::Result<Option<(String, char, u64)>> {
+        /*
+        Reads the next segment of a path, along with its length
+        GFA2 groups may also reference edges or other groups: such references are skipped
+
+        Arguments:
+        - path_type: the type of the path line (P, W, O or U)
+        - seq_lengths: a HashMap with the node names as keys and the sequence lengths as values
+
+        Returns:
+        - the node name, its orientation and its length, or None at the end of the path
+        */
+        while let Some((node, orientation)) = self.read_next_step(path_type)? {
+            match seq_lengths.get(&node) {
+                Some(length) => return Ok(Some((node, orientation, *length))),
+                None if path_type == 'O' || path_type == 'U' => continue,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("path references unknown segment '{}'", node),
+                    ))
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn peek_byte(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.fill_buf()?.first().copied())
+    }
+
+    fn next_path_byte(&mut self) -> io::Result<Option<u8>> {
+        /*
+        Consumes the next byte of a path description, returning None (without consuming) at its end
+        */
+        match self.peek_byte()? {
+            Some(byte) if !is_path_end(byte) => {
+                self.consume(1);
+                Ok(Some(byte))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn is_path_end(byte: u8) -> bool {
+    byte == b'\t' || byte == b'\n' || byte == b'\r'
+}
+
+fn truncated_step(node: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("path step '{}' has no orientation", node),
+    )
+}
+
+pub fn node_offset(position: u64, node_end: u64, node_length: u64, orientation: char) -> u64 {
+    /*
+    Computes the offset of a path position inside a node, on the forward strand of the node
+    On a reverse traversed node, the path enters the node by its end
+
+    Arguments:
+    - position: the position on the path
+    - node_end: the position on the path where the node ends
+    - node_length: the length of the node
+    - orientation: the orientation of the node in the path ('+' or '-')
+    */
+    if orientation == '-' {
+        node_end - position
+    } else {
+        position + node_length - node_end
+    }
+}
+
+pub struct PathReader {
+    reader: GfaReader,
+    path_type: char,
+    // Positions of the lines describing the path, and index of the one being read
+    positions: Vec<u64>,
+    fragment: usize,
+}
+
+impl PathReader {
+    pub fn open(file_path: &str, positions: &[u64], path_type: char) -> io::Result<PathReader> {
+        /*
+        Opens a GFA file and moves to the first node of a path
+
+        Arguments:
+        - file_path: a string with the path to the GFA file
+        - positions: the positions of the lines describing the path, in path order
+          (a path described by several W-lines is read as the concatenation of its fragments)
+        - path_type: the type of the path lines (P, W, O or U)
+        */
+        let mut reader: GfaReader = GfaReader::open(file_path)?;
+        if let Some(&position) = positions.first() {
+            reader.seek_path(position, path_type)?;
+        }
+        Ok(PathReader {
+            reader,
+            path_type,
+            positions: positions.to_vec(),
+            fragment: 0,
+        })
+    }
+
+    pub fn read_next_segment(
+        &mut self,
+        seq_lengths: &HashMap<String, u64>,
+    ) -> io::Result<Option<(String, char, u64)>> {
+        /*
+        Reads the next segment of the path, going on with the next fragment at the end of a line
+        */
+        while self.fragment < self.positions.len() {
+            if let Some(segment) = self.reader.read_next_segment(self.path_type, seq_lengths)? {
+                return Ok(Some(segment));
+            }
+            self.fragment += 1;
+            if let Some(&position) = self.positions.get(self.fragment) {
+                self.reader.seek_path(position, self.path_type)?;
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn expect_next_segment(
+        &mut self,
+        seq_lengths: &HashMap<String, u64>,
+    ) -> io::Result<(String, char, u64)> {
+        /*
+        Reads the next segment of a path that is known to go on (its length is not reached yet)
+        */
+        self.read_next_segment(seq_lengths)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "path ended before reaching its length",
+            )
+        })
+    }
+}
+
+impl Read for GfaReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            GfaReader::Plain(reader) => reader.read(buf),
+            GfaReader::Gzip(reader) => reader.read(buf),
+            GfaReader::Bgzf(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl BufRead for GfaReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            GfaReader::Plain(reader) => reader.fill_buf(),
+            GfaReader::Gzip(reader) => reader.fill_buf(),
+            GfaReader::Bgzf(reader) => reader.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            GfaReader::Plain(reader) => reader.consume(amt),
+            GfaReader::Gzip(reader) => reader.consume(amt),
+            GfaReader::Bgzf(reader) => reader.consume(amt),
+        }
+    }
+}
+
+pub struct PlainReader {
+    file: BufReader<File>,
+    position: u64,
+}
+
+impl PlainReader {
+    fn new(file: File) -> PlainReader {
+        PlainReader {
+            file: BufReader::new(file),
+            position: 0,
+        }
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<()> {
+        self.position = self.file.seek(SeekFrom::Start(position))?;
+        Ok(())
+    }
+}
+
+impl Read for PlainReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read: usize = self.file.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl BufRead for PlainReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.file.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.file.consume(amt);
+        self.position += amt as u64;
+    }
+}
+
+pub struct GzipReader {
+    file_path: String,
+    decoder: BufReader<MultiGzDecoder<File>>,
+    position: u64,
+}
+
+impl GzipReader {
+    fn new(file_path: &str, file: File) -> GzipReader {
+        GzipReader {
+            file_path: file_path.to_string(),
+            decoder: BufReader::new(MultiGzDecoder::new(file)),
+            position: 0,
+        }
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<()> {
+        /*
+        Gzip streams can only be read forward: if the position is behind us,
+        we restart decompression from the start of the file
+        */
+        if position < self.position {
+            *self = GzipReader::new(&self.file_path, File::open(&self.file_path)?);
+        }
+        let to_skip: u64 = position - self.position;
+        let skipped: u64 = io::copy(&mut (&mut self.decoder).take(to_skip), &mut io::sink())?;
+        self.position += skipped;
+        if skipped < to_skip {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "seek past the end of the gzip stream",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Read for GzipReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read: usize = self.decoder.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl BufRead for GzipReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.decoder.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.decoder.consume(amt);
+        self.position += amt as u64;
+    }
+}
+
+pub struct BgzfReader {
+    file: BufReader<File>,
+    // Uncompressed content of the current block
+    block: Vec<u8>,
+    block_position: usize,
+    // Offsets in the compressed file of the current and next blocks
+    block_offset: u64,
+    next_block_offset: u64,
+}
+
+impl BgzfReader {
+    fn new(file: File) -> BgzfReader {
+        BgzfReader {
+            file: BufReader::new(file),
+            block: Vec::new(),
+            block_position: 0,
+            block_offset: 0,
+            next_block_offset: 0,
+        }
+    }
+
+    fn virtual_offset(&self) -> u64 {
+        if !self.block.is_empty() && self.block_position == self.block.len() {
+            // The current block is exhausted: the next block start is the same position
+            self.next_block_offset << 16
+        } else {
+            (self.block_offset << 16) | self.block_position as u64
+        }
+    }
+
+    fn seek(&mut self, virtual_offset: u64) -> io::Result<()> {
+        let block_offset: u64 = virtual_offset >> 16;
+        let block_position: usize = (virtual_offset & 0xffff) as usize;
+        self.file.seek(SeekFrom::Start(block_offset))?;
+        self.next_block_offset = block_offset;
+        self.read_block()?;
+        if block_position > self.block.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "virtual offset outside of BGZF block",
+            ));
+        }
+        self.block_position = block_position;
+        Ok(())
+    }
+
+    fn read_block(&mut self) -> io::Result<bool> {
+        /*
+        Reads and decompresses the block starting at next_block_offset
+        The file must already be positioned at this offset
+
+        Returns:
+        - false if the end of the file was reached, true otherwise
+        */
+        self.block.clear();
+        self.block_position = 0;
+        self.block_offset = self.next_block_offset;
+
+        let mut header: [u8; GZIP_HEADER_LENGTH] = [0; GZIP_HEADER_LENGTH];
+        let read: usize = (&mut self.file)
+            .take(GZIP_HEADER_LENGTH as u64)
+            .read(&mut header)?;
+        if read == 0 {
+            return Ok(false);
+        }
+        if read < GZIP_HEADER_LENGTH {
+            self.file.read_exact(&mut header[read..])?;
+        }
+        let extra_length: usize = u16::from_le_bytes([header[10], header[11]]) as usize;
+        let mut block_data: Vec<u8> = header.to_vec();
+        block_data.resize(GZIP_HEADER_LENGTH + extra_length, 0);
+        self.file
+            .read_exact(&mut block_data[GZIP_HEADER_LENGTH..])?;
+        let block_size: usize = match bgzf_block_size(&block_data) {
+            Some(size) => size,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid BGZF block header",
+                ))
+            }
+        };
+        if block_size < block_data.len() + GZIP_FOOTER_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid BGZF block size",
+            ));
+        }
+        let header_length: usize = block_data.len();
+        block_data.resize(block_size, 0);
+        self.file.read_exact(&mut block_data[header_length..])?;
+
+        let footer: &[u8] = &block_data[block_size - GZIP_FOOTER_LENGTH..];
+        let expected_crc: u32 = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+        let expected_size: u32 = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]);
+        DeflateDecoder::new(&block_data[header_length..block_size - GZIP_FOOTER_LENGTH])
+            .read_to_end(&mut self.block)?;
+        let mut crc: Crc = Crc::new();
+        crc.update(&self.block);
+        if self.block.len() as u32 != expected_size || crc.sum() != expected_crc {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "corrupted BGZF block",
+            ));
+        }
+        self.next_block_offset = self.block_offset + block_size as u64;
+        Ok(true)
+    }
+}
+
+impl Read for BgzfReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available: &[u8] = self.fill_buf()?;
+        let read: usize = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl BufRead for BgzfReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // Empty blocks (such as the end-of-file marker) are skipped
+        while self.block_position >= self.block.len() {
+            if !self.read_block()? {
+                break;
+            }
+        }
+        Ok(&self.block[self.block_position..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.block_position = (self.block_position + amt).min(self.block.len());
+    }
+}
+
+fn bgzf_block_size(header: &[u8]) -> Option<usize> {
+    /*
+    Reads the total size of a BGZF block from its gzip header
+    BGZF blocks are gzip members with an extra field 'BC' holding the block size minus one
+
+    Arguments:
+    - header: the first bytes of the block
+
+    Returns:
+    - the size of the block, or None if the header is not a BGZF header
+    */
+    if header.len() < GZIP_HEADER_LENGTH || header[0..2] != GZIP_MAGIC || header[3] & 0x04 == 0 {
+        return None;
+    }
+    let extra_length: usize = u16::from_le_bytes([header[10], header[11]]) as usize;
+    let extra: &[u8] = header.get(GZIP_HEADER_LENGTH..GZIP_HEADER_LENGTH + extra_length)?;
+    let mut index: usize = 0;
+    while index + 4 <= extra.len() {
+        let subfield_length: usize =
+            u16::from_le_bytes([extra[index + 2], extra[index + 3]]) as usize;
+        if extra[index] == b'B' && extra[index + 1] == b'C' && subfield_length == 2 {
+            let data: &[u8] = extra.get(index + 4..index + 6)?;
+            return Some(u16::from_le_bytes([data[0], data[1]]) as usize + 1);
+        }
+        index += 4 + subfield_length;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn bgzf_block(data: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed: Vec<u8> = encoder.finish().unwrap();
+        let block_size: u16 = (compressed.len() + 18 + 8 - 1) as u16;
+        let mut block: Vec<u8> = vec![
+            0x1f, 0x8b, 8, 4, 0, 0, 0, 0, 0, 0xff, 6, 0, b'B', b'C', 2, 0,
+        ];
+        block.extend_from_slice(&block_size.to_le_bytes());
+        block.extend_from_slice(&compressed);
+        let mut crc: Crc = Crc::new();
+        crc.update(data);
+        block.extend_from_slice(&crc.sum().to_le_bytes());
+        block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        block
+    }
+
+    fn write_temp(name: &str, content: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn read_lines_with_positions(reader: &mut GfaReader) -> Vec<(u64, String)> {
+        let mut lines: Vec<(u64, String)> = Vec::new();
+        let mut line: String = String::new();
+        loop {
+            let position: u64 = reader.tell().unwrap();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+            lines.push((position, line.clone()));
+            line.clear();
+        }
+        lines
+    }
+
+    const CONTENT: &str =
+        "S\t1\tACGT\nS\t2\tA\nP\tx\t1+,2-\t*\nW\ty\t0\tz\t0\t5\t>1<2\nO\tg\t1+ e1+ 2-\tLN:i:5\n";
+
+    fn read_steps(reader: &mut GfaReader, path_type: char) -> Vec<(String, char)> {
+        let mut steps: Vec<(String, char)> = Vec::new();
+        while let Some(step) = reader.read_next_step(path_type).unwrap() {
+            steps.push(step);
+        }
+        steps
+    }
+
+    #[test]
+    fn test_read_steps() {
+        let path: String = write_temp("steps.gfa", CONTENT.as_bytes());
+        let mut reader: GfaReader = GfaReader::open(&path).unwrap();
+        let lines: Vec<(u64, String)> = read_lines_with_positions(&mut reader);
+        let expected: Vec<(String, char)> = vec![("1".to_string(), '+'), ("2".to_string(), '-')];
+        reader.seek_path(lines[2].0, 'P').unwrap();
+        assert_eq!(read_steps(&mut reader, 'P'), expected);
+        reader.seek_path(lines[3].0, 'W').unwrap();
+        assert_eq!(read_steps(&mut reader, 'W'), expected);
+        // The end of the path is reported again, without reading the next line
+        assert_eq!(reader.read_next_step('W').unwrap(), None);
+        // The edge e1 is skipped from the O-group
+        let seq_lengths: HashMap<String, u64> =
+            HashMap::from([("1".to_string(), 4), ("2".to_string(), 1)]);
+        reader.seek_path(lines[4].0, 'O').unwrap();
+        assert_eq!(
+            reader.read_next_segment('O', &seq_lengths).unwrap(),
+            Some(("1".to_string(), '+', 4))
+        );
+        assert_eq!(
+            reader.read_next_segment('O', &seq_lengths).unwrap(),
+            Some(("2".to_string(), '-', 1))
+        );
+        assert_eq!(reader.read_next_segment('O', &seq_lengths).unwrap(), None);
+        // Fragments are read one after the other
+        let mut path_reader: PathReader =
+            PathReader::open(&path, &[lines[3].0, lines[3].0], 'W').unwrap();
+        let mut nodes: Vec<String> = Vec::new();
+        while let Some((node, _, _)) = path_reader.read_next_segment(&seq_lengths).unwrap() {
+            nodes.push(node);
+        }
+        assert_eq!(nodes, vec!["1", "2", "1", "2"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_node_offset() {
+        // Node of length 10 spanning positions 20 to 30 on the path, breakpoint at 23
+        assert_eq!(node_offset(23, 30, 10, '+'), 3);
+        assert_eq!(node_offset(23, 30, 10, '-'), 7);
+    }
+
+    #[test]
+    fn test_bgzf_block_size() {
+        let block: Vec<u8> = bgzf_block(b"S\t1\tA\n");
+        assert_eq!(bgzf_block_size(&block), Some(block.len()));
+        assert_eq!(bgzf_block_size(b"S\t1\tA\n"), None);
+    }
+
+    #[test]
+    fn test_plain_and_gzip_seek() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        for path in [
+            write_temp("plain.gfa", CONTENT.as_bytes()),
+            write_temp("gzip.gfa.gz", &encoder.finish().unwrap()),
+        ] {
+            let mut reader: GfaReader = GfaReader::open(&path).unwrap();
+            let lines: Vec<(u64, String)> = read_lines_with_positions(&mut reader);
+            assert_eq!(lines.len(), 5);
+            reader.seek(lines[2].0).unwrap();
+            let mut line: String = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, lines[2].1);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_bgzf_seek_across_blocks() {
+        // Lines are split over several blocks, followed by the empty end-of-file block
+        let (first, second) = CONTENT.split_at(20);
+        let mut content: Vec<u8> = bgzf_block(first.as_bytes());
+        content.extend(bgzf_block(second.as_bytes()));
+        content.extend(bgzf_block(b""));
+        let path: String = write_temp("bgzf.gfa.gz", &content);
+
+        let mut reader: GfaReader = GfaReader::open(&path).unwrap();
+        assert!(matches!(reader, GfaReader::Bgzf(_)));
+        let lines: Vec<(u64, String)> = read_lines_with_positions(&mut reader);
+        assert_eq!(
+            lines.iter().map(|(_, l)| l.as_str()).collect::<String>(),
+            CONTENT
+        );
+        for (position, expected) in lines.iter().rev() {
+            reader.seek(*position).unwrap();
+            let mut line: String = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(&line, expected);
+        }
+        reader.seek_path(lines[3].0, 'W').unwrap();
+        let mut rest: String = String::new();
+        reader.read_line(&mut rest).unwrap();
+        assert_eq!(rest, ">1<2\n");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use crate::index_gfa_file::GfaIndex;
+use crate::read_gfa_file::PathReader;
+use std::io;
+// The segmentation of a path is the list of nodes it goes through, in path order.
+// Breakpoints are the positions on the path where a node ends, the last one being the length of the path.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathSegmentation {
+    pub path_name: String,
+    // Node names, orientations and lengths, in path order
+    pub nodes: Vec<(String, char, u64)>,
+}
+
+impl PathSegmentation {
+    pub fn read(
+        file_path: &str,
+        index: &GfaIndex,
+        path_name: &str,
+    ) -> io::Result<PathSegmentation> {
+        /*
+        Reads the segmentation of a path from a GFA file
+
+        Arguments:
+        - file_path: the path to the GFA file
+        - index: the index of the GFA file
+        - path_name: the name of the path in the index
+
+        Returns:
+        - the nodes of the path, up to the length of the path
+        */
+        let positions: &Vec<u64> = index.path_positions.get(path_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("path {} is not in {}", path_name, file_path),
+            )
+        })?;
+        let mut reader: PathReader =
+            PathReader::open(file_path, positions, index.path_types[path_name])?;
+        let max_length: u64 = index.path_lengths[path_name];
+        let mut nodes: Vec<(String, char, u64)> = Vec::new();
+        let mut cum_length: u64 = 0;
+        while cum_length < max_length {
+            // We read the next node
+            let node: (String, char, u64) = reader.expect_next_segment(&index.seq_lengths)?;
+            cum_length += node.2;
+            nodes.push(node);
+        }
+        Ok(PathSegmentation {
+            path_name: path_name.to_string(),
+            nodes,
+        })
+    }
+
+    pub fn length(&self) -> u64 {
+        self.nodes.iter().map(|(_, _, length)| length).sum()
+    }
+
+    pub fn breakpoints(&self) -> Vec<u64> {
+        /*
+        Returns the positions where each node of the path ends
+        */
+        self.nodes
+            .iter()
+            .scan(0, |cum_length: &mut u64, (_, _, length)| {
+                *cum_length += length;
+                Some(*cum_length)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_gfa_file::parse_gfa;
+
+    #[test]
+    fn test_path_segmentation() {
+        let gfa_path =
+            std::env::temp_dir().join(format!("{}_segmentation.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        std::fs::write(gfa_path, "S\t1\tACGT\nS\t2\tA\nP\tx\t1+,2-,1+\t*\n").unwrap();
+        let index: GfaIndex = parse_gfa(gfa_path).unwrap();
+
+        let segmentation: PathSegmentation = PathSegmentation::read(gfa_path, &index, "x").unwrap();
+        assert_eq!(segmentation.nodes[1], ("2".to_string(), '-', 1));
+        assert_eq!(segmentation.length(), 9);
+        assert_eq!(segmentation.breakpoints(), vec![4, 5, 9]);
+        assert!(PathSegmentation::read(gfa_path, &index, "y").is_err());
+        std::fs::remove_file(gfa_path).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+// Regions are intervals on paths, used to select the parts of paths that are compared, or to mask them, given as PATH:START-END or as lines of a BED file (path, start, end).
+// Coordinates are positions on the path as reported in the edit file: 0-based, end excluded, as in BED files.
+// Intervals of a path are kept sorted and merged, so that a position can be found by binary search.
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Regions {
+    // Path names as keys and sorted, non-overlapping intervals as values
+    intervals: HashMap<String, Vec<(u64, u64)>>,
+}
+
+impl Regions {
+    pub fn new(regions: Vec<(String, u64, u64)>) -> Regions {
+        /*
+        Builds the regions from a list of intervals, in any order and possibly overlapping
+
+        Arguments:
+        - regions: a vector of (path name, start, end)
+
+        Returns:
+        - the regions, with intervals of each path sorted and merged
+        */
+        let mut intervals: HashMap<String, Vec<(u64, u64)>> = HashMap::new();
+        for (path_name, start, end) in regions {
+            if start < end {
+                intervals.entry(path_name).or_default().push((start, end));
+            }
+        }
+        for path_intervals in intervals.values_mut() {
+            path_intervals.sort();
+            let mut merged: Vec<(u64, u64)> = Vec::with_capacity(path_intervals.len());
+            for &(start, end) in path_intervals.iter() {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            *path_intervals = merged;
+        }
+        Regions { intervals }
+    }
+
+    pub fn path_names(&self) -> Vec<&String> {
+        /*
+        Returns the names of the paths holding a region, sorted
+        */
+        let mut path_names: Vec<&String> = self.intervals.keys().collect();
+        path_names.sort();
+        path_names
+    }
+
+    pub fn path_intervals(&self, path_name: &str) -> Option<&[(u64, u64)]> {
+        self.intervals.get(path_name).map(|v| v.as_slice())
+    }
+}
+
+pub fn contains(intervals: &[(u64, u64)], position: u64) -> bool {
+    /*
+    Tells if a position falls inside one of the sorted, non-overlapping intervals
+    */
+    // Index of the first interval starting after the position
+    let index: usize = intervals.partition_point(|&(start, _)| start <= position);
+    index > 0 && position < intervals[index - 1].1
+}
+
+pub fn covered_length(intervals: &[(u64, u64)], length: u64) -> u64 {
+    /*
+    Returns the number of positions of a path of the given length covered by the intervals
+    */
+    intervals
+        .iter()
+        .map(|&(start, end)| end.min(length).saturating_sub(start))
+        .sum()
+}
+
+pub fn intersect(intervals1: &[(u64, u64)], intervals2: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    /*
+    Returns the intervals covered by both sets of sorted, non-overlapping intervals
+    */
+    let mut intersection: Vec<(u64, u64)> = Vec::new();
+    let (mut i, mut j): (usize, usize) = (0, 0);
+    while i < intervals1.len() && j < intervals2.len() {
+        let start: u64 = intervals1[i].0.max(intervals2[j].0);
+        let end: u64 = intervals1[i].1.min(intervals2[j].1);
+        if start < end {
+            intersection.push((start, end));
+        }
+        // We move forward on the interval ending first
+        if intervals1[i].1 < intervals2[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    intersection
+}
+
+pub fn parse_region(region: &str) -> io::Result<(String, u64, u64)> {
+    /*
+    Parses a region given as PATH:START-END
+    The path name may contain ':', the coordinates are read after the last one
+
+    Arguments:
+    - region: the region string
+
+    Returns:
+    - the path name, start and end of the region
+    */
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid region {} (expected PATH:START-END)", region),
+        )
+    };
+    let (path_name, coordinates) = region.rsplit_once(':').ok_or_else(invalid)?;
+    let (start, end) = coordinates.split_once('-').ok_or_else(invalid)?;
+    let start: u64 = start.replace(',', "").parse().map_err(|_| invalid())?;
+    let end: u64 = end.replace(',', "").parse().map_err(|_| invalid())?;
+    if path_name.is_empty() || start >= end {
+        return Err(invalid());
+    }
+    Ok((path_name.to_string(), start, end))
+}
+
+pub fn read_bed(bed_file: &str) -> io::Result<Vec<(String, u64, u64)>> {
+    /*
+    Reads the intervals of a BED file, whose first column is a path name
+    Comment, track and browser lines are skipped, columns after the third one are ignored
+
+    Arguments:
+    - bed_file: the path to the BED file
+
+    Returns:
+    - a vector of (path name, start, end)
+    */
+    let mut reader: BufReader<File> = BufReader::new(File::open(bed_file)?);
+    let mut regions: Vec<(String, u64, u64)> = Vec::new();
+    let mut line: String = String::new();
+    let mut line_number: usize = 0;
+    while reader.read_line(&mut line)? > 0 {
+        line_number += 1;
+        let content: &str = line.trim_end_matches(['\n', '\r']);
+        if !content.is_empty()
+            && !content.starts_with('#')
+            && !content.starts_with("track")
+            && !content.starts_with("browser")
+        {
+            let columns: Vec<&str> = content.split('\t').collect();
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid BED line {} in {}", line_number, bed_file),
+                )
+            };
+            if columns.len() < 3 {
+                return Err(invalid());
+            }
+            let start: u64 = columns[1].parse().map_err(|_| invalid())?;
+            let end: u64 = columns[2].parse().map_err(|_| invalid())?;
+            regions.push((columns[0].to_string(), start, end));
+        }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(
+            parse_region("HG002#1#chr6:28,510,120-33,480,577").unwrap(),
+            ("HG002#1#chr6".to_string(), 28510120, 33480577)
+        );
+        assert!(parse_region("chr6").is_err());
+        assert!(parse_region("chr6:20-10").is_err());
+    }
+
+    #[test]
+    fn test_regions_merge_and_contains() {
+        let regions: Regions = Regions::new(vec![
+            ("x".to_string(), 10, 20),
+            ("x".to_string(), 0, 5),
+            ("x".to_string(), 15, 30),
+            ("y".to_string(), 3, 3),
+        ]);
+        let intervals: &[(u64, u64)] = regions.path_intervals("x").unwrap();
+        assert_eq!(intervals, &[(0, 5), (10, 30)]);
+        assert!(regions.path_intervals("y").is_none());
+        assert!(contains(intervals, 0));
+        assert!(!contains(intervals, 5));
+        assert!(contains(intervals, 29));
+        assert!(!contains(intervals, 30));
+        assert_eq!(covered_length(intervals, 12), 7);
+        assert_eq!(
+            intersect(intervals, &[(3, 12), (25, 40)]),
+            vec![(3, 5), (10, 12), (25, 30)]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::index_gfa_file::GfaIndex;
+use flate2::Crc;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+// The index of a GFA file can be stored in a sidecar file (graph.gfa -> graph.gfa.pci), to avoid parsing the graph on every run.
+// The sidecar file is a TSV file:
+// - a header line with the format version
+// - a C-line with the size, modification time and CRC32 checksum of the GFA file, the index is only used if they match the GFA file
+// - S-lines with segment names and lengths
+// - P-lines with path names, types, lengths and positions of the path lines (comma-separated)
+// - G-lines with gaps between fragments of paths
+// Path names are stored as they are written in the GFA file, so the index does not depend on path naming options.
+
+const INDEX_EXTENSION: &str = "pci";
+// Version 2 added path types and gaps, and the modification time of the GFA file
+// Version 3 checksums the whole GFA file
+const INDEX_HEADER: &str = "# rs-pancat-compare index\t3";
+
+pub fn index_path(file_path: &str) -> String {
+    /*
+    Returns the path of the sidecar index file of a GFA file
+    */
+    format!("{}.{}", file_path, INDEX_EXTENSION)
+}
+
+pub fn file_checksum(file_path: &str) -> io::Result<(u64, u128, u32)> {
+    /*
+    Computes the size, the modification time and the CRC32 checksum of a file, as stored bytes (compressed files are not decompressed)
+
+    Arguments:
+    - file_path: the path to the file
+
+    Returns:
+    - the size of the file, its modification time (in nanoseconds since the Unix epoch) and its checksum
+    */
+    let mut file: File = File::open(file_path)?;
+    let metadata: std::fs::Metadata = file.metadata()?;
+    let size: u64 = metadata.len();
+    let modified: u128 = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos());
+    let mut crc: Crc = Crc::new();
+    let mut buffer: Vec<u8> = vec![0; 1 << 16];
+    loop {
+        let read: usize = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        crc.update(&buffer[..read]);
+    }
+    Ok((size, modified, crc.sum()))
+}
+
+pub fn write_index(file_path: &str, index: &GfaIndex) -> io::Result<String> {
+    /*
+    Writes the index of a GFA file to its sidecar file
+
+    Arguments:
+    - file_path: the path to the GFA file
+    - index: the index of the GFA file, with path names as written in the file
+
+    Returns:
+    - the path of the sidecar file
+    */
+    let (size, modified, checksum) = file_checksum(file_path)?;
+    let output_path: String = index_path(file_path);
+    let mut writer: BufWriter<File> = BufWriter::new(File::create(&output_path)?);
+
+    writeln!(writer, "{}", INDEX_HEADER)?;
+    writeln!(writer, "C\t{}\t{}\t{}", size, modified, checksum)?;
+    for (node_name, length) in index.seq_lengths.iter() {
+        writeln!(writer, "S\t{}\t{}", node_name, length)?;
+    }
+    for (path_name, positions) in index.path_positions.iter() {
+        let positions: Vec<String> = positions.iter().map(|p| p.to_string()).collect();
+        writeln!(
+            writer,
+            "P\t{}\t{}\t{}\t{}",
+            path_name,
+            index.path_types[path_name],
+            index.path_lengths[path_name],
+            positions.join(",")
+        )?;
+    }
+    for (path_name, gaps) in index.path_gaps.iter() {
+        for (position, previous_end, next_start) in gaps.iter() {
+            writeln!(
+                writer,
+                "G\t{}\t{}\t{}\t{}",
+                path_name, position, previous_end, next_start
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(output_path)
+}
+
+pub fn read_index(file_path: &str) -> io::Result<Option<GfaIndex>> {
+    /*
+    Reads the sidecar index file of a GFA file, if there is one and it matches the GFA file
+
+    Arguments:
+    - file_path: the path to the GFA file
+
+    Returns:
+    - the index of the GFA file, or None if there is no sidecar file
+    - an error if the sidecar file can't be read or if the GFA file changed since it was written
+    */
+    let sidecar_path: String = index_path(file_path);
+    if !Path::new(&sidecar_path).exists() {
+        return Ok(None);
+    }
+    let mut reader: BufReader<File> = BufReader::new(File::open(&sidecar_path)?);
+    let mut line: String = String::new();
+
+    reader.read_line(&mut line)?;
+    if line.trim_end() != INDEX_HEADER {
+        // Indexes written by other versions may miss fields, the graph is parsed again
+        return Err(invalid_index("unknown index format, index the graph again"));
+    }
+    line.clear();
+    reader.read_line(&mut line)?;
+    let columns: Vec<&str> = line.trim_end().split('\t').collect();
+    if columns.len() != 4 || columns[0] != "C" {
+        return Err(invalid_index("missing checksum"));
+    }
+    let (size, modified, checksum) = file_checksum(file_path)?;
+    if columns[1] != size.to_string()
+        || columns[2] != modified.to_string()
+        || columns[3] != checksum.to_string()
+    {
+        return Err(invalid_index("the graph changed since it was indexed"));
+    }
+
+    let mut index: GfaIndex = GfaIndex {
+        seq_lengths: HashMap::new(),
+        path_positions: HashMap::new(),
+        path_lengths: HashMap::new(),
+        path_types: HashMap::new(),
+        path_gaps: HashMap::new(),
+    };
+    line.clear();
+    while reader.read_line(&mut line)? > 0 {
+        let columns: Vec<&str> = line.trim_end_matches(['\n', '\r']).split('\t').collect();
+        match (columns[0], columns.len()) {
+            ("S", 3) => {
+                index
+                    .seq_lengths
+                    .insert(columns[1].to_string(), parse_number(columns[2])?);
+            }
+            ("P", 5) => {
+                let path_name: String = columns[1].to_string();
+                let path_type: char = columns[2]
+                    .chars()
+                    .next()
+                    .ok_or_else(|| invalid_index("missing path type"))?;
+                let positions: Vec<u64> = columns[4]
+                    .split(',')
+                    .map(parse_number)
+                    .collect::<io::Result<Vec<u64>>>()?;
+                index.path_types.insert(path_name.clone(), path_type);
+                index
+                    .path_lengths
+                    .insert(path_name.clone(), parse_number(columns[3])?);
+                index.path_positions.insert(path_name, positions);
+            }
+            ("G", 5) => {
+                index
+                    .path_gaps
+                    .entry(columns[1].to_string())
+                    .or_default()
+                    .push((
+                        parse_number(columns[2])?,
+                        parse_number(columns[3])?,
+                        parse_number(columns[4])?,
+                    ));
+            }
+            _ => return Err(invalid_index("malformed line")),
+        }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    Ok(Some(index))
+}
+
+fn parse_number(column: &str) -> io::Result<u64> {
+    column
+        .parse::<u64>()
+        .map_err(|_| invalid_index("invalid number"))
+}
+
+fn invalid_index(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_gfa_file::parse_gfa;
+
+    #[test]
+    fn test_write_and_read_index() {
+        let gfa_path = std::env::temp_dir().join(format!("{}_index.gfa", std::process::id()));
+        let gfa_path: &str = gfa_path.to_str().unwrap();
+        std::fs::write(
+            gfa_path,
+            "S\t1\tACGT\nS\t2\t*\tLN:i:3\nW\ts\t1\tc\t10\t15\t>1<2\nW\ts\t1\tc\t0\t5\t>2>1\n",
+        )
+        .unwrap();
+        let index: GfaIndex = parse_gfa(gfa_path).unwrap();
+        let sidecar_path: String = write_index(gfa_path, &index).unwrap();
+
+        let stored: GfaIndex = read_index(gfa_path).unwrap().unwrap();
+        assert_eq!(stored.seq_lengths, index.seq_lengths);
+        assert_eq!(stored.path_positions, index.path_positions);
+        assert_eq!(stored.path_lengths, index.path_lengths);
+        assert_eq!(stored.path_types, index.path_types);
+        assert_eq!(stored.path_gaps, index.path_gaps);
+        assert_eq!(stored.path_gaps["s#1#c"], vec![(7, 5, 10)]);
+
+        // An index written by another version is rejected
+        let sidecar: String = std::fs::read_to_string(&sidecar_path).unwrap();
+        std::fs::write(&sidecar_path, sidecar.replace("index\t3", "index\t2")).unwrap();
+        assert!(read_index(gfa_path).is_err());
+        write_index(gfa_path, &index).unwrap();
+
+        // A change in the middle of the graph is caught, even if its size and modification time are kept
+        let modified: std::time::SystemTime =
+            std::fs::metadata(gfa_path).unwrap().modified().unwrap();
+        let graph: String = std::fs::read_to_string(gfa_path).unwrap();
+        std::fs::write(gfa_path, graph.replacen("ACGT", "ACGA", 1)).unwrap();
+        File::options()
+            .write(true)
+            .open(gfa_path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert!(read_index(gfa_path).is_err());
+
+        // The index is not used anymore once the graph changed
+        std::fs::write(gfa_path, "S\t1\tACGT\n").unwrap();
+        assert!(read_index(gfa_path).is_err());
+        std::fs::remove_file(gfa_path).unwrap();
+        std::fs::remove_file(sidecar_path).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+use crate::compute_distance::Operation;
+use crate::index_gfa_file::GfaIndex;
+use crate::segment_paths::PathSegmentation;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+// An edit file can be checked against the two graphs it was computed from: the edits of each path are applied
+// to the breakpoints of the path in the first graph, and the result must be the breakpoints of the path in the second graph.
+// A split (S) at a position is a breakpoint of the first graph that the second graph does not have, so applying it removes
+// the breakpoint; a merge (M) is a breakpoint of the second graph that the first graph does not have, so applying it adds the breakpoint.
+
+// Name, number of edits and first diverging position of a verified path
+pub type PathVerification = (String, usize, Option<u64>);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EditScript {
+    // Paths compared, in the order of the edit file
+    pub paths: Vec<String>,
+    // Path names as keys and positions and operations of their edits as values
+    pub edits: HashMap<String, Vec<(u64, Operation)>>,
+}
+
+pub fn read_edits(edit_file: &str) -> io::Result<EditScript> {
+    /*
+    Reads the paths and the edits of an edit file (TSV output)
+
+    Arguments:
+    - edit_file: the path to the edit file
+
+    Returns:
+    - the paths listed in the header ('##' lines) and the edits of each path
+    */
+    let mut reader: BufReader<File> = BufReader::new(File::open(edit_file)?);
+    let mut script: EditScript = EditScript {
+        paths: Vec::new(),
+        edits: HashMap::new(),
+    };
+    let mut line: String = String::new();
+    let mut line_number: usize = 0;
+    while reader.read_line(&mut line)? > 0 {
+        line_number += 1;
+        let content: &str = line.trim_end_matches(['\n', '\r']);
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid edit line {} in {}", line_number, edit_file),
+            )
+        };
+        if let Some(path) = content.strip_prefix("## ") {
+            // Length of a path in the intersection
+            let (path_name, _) = path.split_once('\t').ok_or_else(invalid)?;
+            script.paths.push(path_name.to_string());
+        } else if !content.is_empty() && !content.starts_with('#') {
+            let columns: Vec<&str> = content.split('\t').collect();
+            if columns.len() < 3 {
+                return Err(invalid());
+            }
+            let position: u64 = columns[1].parse().map_err(|_| invalid())?;
+            let op: Operation = match columns[2] {
+                "S" => Operation::Split,
+                "M" => Operation::Merge,
+                _ => return Err(invalid()),
+            };
+            script
+                .edits
+                .entry(columns[0].to_string())
+                .or_default()
+                .push((position, op));
+        }
+        line.clear(); // Clear the line buffer for the next read
+    }
+    Ok(script)
+}
+
+pub fn first_divergence(
+    breakpoints_a: &[u64],
+    breakpoints_b: &[u64],
+    edits: &[(u64, Operation)],
+) -> Option<u64> {
+    /*
+    Applies edits to the breakpoints of a path in the first graph, and compares them with the breakpoints in the second graph
+
+    Arguments:
+    - breakpoints_a: the breakpoints of the path in the first graph
+    - breakpoints_b: the breakpoints of the path in the second graph
+    - edits: the positions and operations of the edits of the path
+
+    Returns:
+    - the first position where the edited breakpoints differ from the second graph, or where an edit cannot be applied,
+      None if the second graph is reproduced
+    */
+    let mut breakpoints: BTreeSet<u64> = breakpoints_a.iter().copied().collect();
+    let mut divergence: Option<u64> = None;
+    for &(position, op) in edits {
+        let applied: bool = match op {
+            Operation::Split => breakpoints.remove(&position),
+            Operation::Merge => breakpoints.insert(position),
+        };
+        if !applied {
+            // The breakpoint to remove is missing, or the breakpoint to add is already there
+            divergence = Some(divergence.map_or(position, |d| d.min(position)));
+        }
+    }
+    let expected: BTreeSet<u64> = breakpoints_b.iter().copied().collect();
+    if let Some(&position) = breakpoints.symmetric_difference(&expected).next() {
+        divergence = Some(divergence.map_or(position, |d| d.min(position)));
+    }
+    divergence
+}
+
+pub fn verify(
+    file_path1: &str,
+    file_path2: &str,
+    index1: &GfaIndex,
+    index2: &GfaIndex,
+    edit_file: &str,
+) -> io::Result<(Vec<PathVerification>, Vec<String>)> {
+    /*
+    Checks that the edits of an edit file transform the segmentation of each path of the first graph into its segmentation in the second graph
+
+    Arguments:
+    - file_path1, file_path2: the paths to the GFA files the edit file was computed from
+    - index1, index2: the indexes of the GFA files
+    - edit_file: the path to the edit file
+
+    Output:
+    - Writes to standard output, for each path, its number of edits and whether the second graph is reproduced,
+      with the first diverging position if not
+
+    Returns:
+    - the name, number of edits and first diverging position of each path
+    - warnings about paths with edits missing from the header, and paths that can't be verified
+    */
+    let mut script: EditScript = read_edits(edit_file)?;
+    let mut warnings: Vec<String> = Vec::new();
+    // Paths are listed in a fixed order, so warnings do not change between runs
+    let mut edited_paths: Vec<&String> = script.edits.keys().collect();
+    edited_paths.sort();
+    for path_name in edited_paths {
+        if !script.paths.contains(path_name) {
+            warnings.push(format!(
+                "edits on path {} which is not in the header of {}",
+                path_name, edit_file
+            ));
+        }
+    }
+    let mut writer: io::BufWriter<io::Stdout> = io::BufWriter::new(io::stdout());
+    writeln!(writer, "# Path name\tEdits\tStatus\tPosition")?;
+    let mut results: Vec<PathVerification> = Vec::new();
+    for path_name in script.paths.iter() {
+        let segmentation1: PathSegmentation =
+            PathSegmentation::read(file_path1, index1, path_name)?;
+        let segmentation2: PathSegmentation =
+            PathSegmentation::read(file_path2, index2, path_name)?;
+        if segmentation1.length() != segmentation2.length() {
+            // The path was skipped when computing the edit file
+            warnings.push(format!(
+                "the two paths representing {} have different lengths, they are not verified",
+                path_name
+            ));
+            continue;
+        }
+        let edits: Vec<(u64, Operation)> = script.edits.remove(path_name).unwrap_or_default();
+        let divergence: Option<u64> = first_divergence(
+            &segmentation1.breakpoints(),
+            &segmentation2.breakpoints(),
+            &edits,
+        );
+        match divergence {
+            Some(position) => writeln!(
+                writer,
+                "{}\t{}\tDIVERGES\t{}",
+                path_name,
+                edits.len(),
+                position
+            )?,
+            None => writeln!(writer, "{}\t{}\tOK\t*", path_name, edits.len())?,
+        }
+        results.push((path_name.to_string(), edits.len(), divergence));
+    }
+    let diverging: usize = results.iter().filter(|(_, _, d)| d.is_some()).count();
+    writeln!(
+        writer,
+        "# Verified: {} paths, {} reproduced, {} diverging.",
+        results.len(),
+        results.len() - diverging,
+        diverging
+    )?;
+    writer.flush()?;
+    Ok((results, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_divergence() {
+        // A cuts the path at 2, 4 and 5, B cuts it at 3, 4 and 5
+        let (breakpoints_a, breakpoints_b): (Vec<u64>, Vec<u64>) = (vec![2, 4, 5], vec![3, 4, 5]);
+        let edits: Vec<(u64, Operation)> = vec![(2, Operation::Split), (3, Operation::Merge)];
+        assert_eq!(
+            first_divergence(&breakpoints_a, &breakpoints_b, &edits),
+            None
+        );
+        // The merge is missing
+        assert_eq!(
+            first_divergence(&breakpoints_a, &breakpoints_b, &edits[..1]),
+            Some(3)
+        );
+        // A split where the first graph has no breakpoint cannot be applied
+        let edits: Vec<(u64, Operation)> = vec![
+            (1, Operation::Split),
+            (2, Operation::Split),
+            (3, Operation::Merge),
+        ];
+        assert_eq!(
+            first_divergence(&breakpoints_a, &breakpoints_b, &edits),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_read_edits() {
+        let edit_path = std::env::temp_dir().join(format!("{}_edits.tsv", std::process::id()));
+        let edit_path: &str = edit_path.to_str().unwrap();
+        std::fs::write(
+            edit_path,
+            "# Intersection of paths: [\"x\"]\n## x\t5\n# Path name\tPosition\tOperation\nx\t2\tS\t1\t4\nx\t3\tM\t2\t4\n# Distance: 2 (E=2, S=1, M=1).\n",
+        )
+        .unwrap();
+        let script: EditScript = read_edits(edit_path).unwrap();
+        assert_eq!(script.paths, vec!["x".to_string()]);
+        assert_eq!(
+            script.edits["x"],
+            vec![(2, Operation::Split), (3, Operation::Merge)]
+        );
+        std::fs::remove_file(edit_path).unwrap();
+    }
+}
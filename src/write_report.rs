@@ -0,0 +1,470 @@
+use crate::compute_distance::{DistanceReport, Edit, PathSummary};
+use crate::index_gfa_file::GfaIndex;
+use std::io::{self, Write};
+use std::str::FromStr;
+// The distance report can be written in three formats:
+// - tsv: the edit TSV, with information about the comparison in lines starting with '#'
+// - json: a single JSON document holding the intersection, path lengths, gaps, edits, skipped paths, per-path summaries and summary
+// - jsonl: one JSON record per line, each record having a "type" field (intersection, path, gap, edit, skipped_path, path_summary, summary)
+// Edits are written as soon as they are computed, in every format.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Tsv,
+    Json,
+    JsonLines,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<OutputFormat, String> {
+        match name {
+            "tsv" => Ok(OutputFormat::Tsv),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::JsonLines),
+            _ => Err(format!(
+                "unknown format {} (expected tsv, json or jsonl)",
+                name
+            )),
+        }
+    }
+}
+
+pub struct ReportWriter<W: Write> {
+    writer: W,
+    format: OutputFormat,
+    // Number of edits written, to separate them in the JSON document
+    edits_written: u64,
+    // Paths that could not be compared, written after the edits in the JSON document
+    skipped_paths: Vec<(String, u64, u64)>,
+}
+
+impl<W: Write> ReportWriter<W> {
+    pub fn new(writer: W, format: OutputFormat) -> ReportWriter<W> {
+        ReportWriter {
+            writer,
+            format,
+            edits_written: 0,
+            skipped_paths: Vec::new(),
+        }
+    }
+
+    pub fn header(
+        &mut self,
+        intersection: &[String],
+        index1: &GfaIndex,
+        index2: &GfaIndex,
+    ) -> io::Result<()> {
+        /*
+        Writes information about the comparison, before any edit
+
+        Arguments:
+        - intersection: the paths found in both graphs
+        - index1: the index of the first GFA file
+        - index2: the index of the second GFA file
+        */
+        // Gaps between fragments of paths in the intersection: graph, path name, position, previous end, next start
+        let mut gaps: Vec<(&str, &str, u64, u64, u64)> = Vec::new();
+        for (graph, index) in [("A", index1), ("B", index2)] {
+            for path_name in intersection.iter() {
+                for (position, previous_end, next_start) in
+                    index.path_gaps.get(path_name).into_iter().flatten()
+                {
+                    gaps.push((graph, path_name, *position, *previous_end, *next_start));
+                }
+            }
+        }
+        match self.format {
+            OutputFormat::Tsv => {
+                writeln!(self.writer, "# Intersection of paths: {:?}", intersection)?;
+                // Print the length of every path in the intersection
+                for path_name in intersection.iter() {
+                    writeln!(
+                        self.writer,
+                        "## {}\t{}",
+                        path_name, index1.path_lengths[path_name]
+                    )?;
+                }
+                for (graph, path_name, position, previous_end, next_start) in gaps {
+                    writeln!(
+                        self.writer,
+                        "# Gap\t{}\t{}\t{}\t{}\t{}",
+                        graph, path_name, position, previous_end, next_start
+                    )?;
+                }
+                writeln!(self.writer, "# Path name\tPosition\tOperation\tNodeA\tNodeB\tBreakpointA\tBreakpointB\tStrandA\tStrandB\tOffsetA\tOffsetB")?;
+            }
+            OutputFormat::Json => {
+                let paths: Vec<String> = intersection.iter().map(|p| json_string(p)).collect();
+                let lengths: Vec<String> = intersection
+                    .iter()
+                    .map(|p| path_length_record(p, index1.path_lengths[p]))
+                    .collect();
+                let gaps: Vec<String> = gaps.into_iter().map(gap_record).collect();
+                write!(
+                    self.writer,
+                    "{{\"intersection\":[{}],\"paths\":[{}],\"gaps\":[{}],\"edits\":[",
+                    paths.join(","),
+                    lengths.join(","),
+                    gaps.join(",")
+                )?;
+            }
+            OutputFormat::JsonLines => {
+                let paths: Vec<String> = intersection.iter().map(|p| json_string(p)).collect();
+                writeln!(
+                    self.writer,
+                    "{{\"type\":\"intersection\",\"paths\":[{}]}}",
+                    paths.join(",")
+                )?;
+                for path_name in intersection.iter() {
+                    writeln!(
+                        self.writer,
+                        "{{\"type\":\"path\",{}",
+                        &path_length_record(path_name, index1.path_lengths[path_name])[1..]
+                    )?;
+                }
+                for gap in gaps {
+                    writeln!(self.writer, "{{\"type\":\"gap\",{}", &gap_record(gap)[1..])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn edit(&mut self, edit: &Edit) -> io::Result<()> {
+        /*
+        Writes an edit operation
+        */
+        match self.format {
+            OutputFormat::Tsv => writeln!(
+                self.writer,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                edit.path,
+                edit.position,
+                edit.op.as_char(),
+                edit.node_a,
+                edit.node_b,
+                edit.breakpoint_a,
+                edit.breakpoint_b,
+                edit.strand_a,
+                edit.strand_b,
+                edit.offset_a,
+                edit.offset_b
+            )?,
+            OutputFormat::Json => {
+                if self.edits_written > 0 {
+                    write!(self.writer, ",")?;
+                }
+                write!(self.writer, "\n{}", edit_record(edit))?;
+            }
+            OutputFormat::JsonLines => writeln!(
+                self.writer,
+                "{{\"type\":\"edit\",{}",
+                &edit_record(edit)[1..]
+            )?,
+        }
+        self.edits_written += 1;
+        Ok(())
+    }
+
+    pub fn skipped_path(&mut self, path_name: &str, length1: u64, length2: u64) -> io::Result<()> {
+        /*
+        Writes a path that could not be compared, as its two versions have different lengths
+        */
+        match self.format {
+            OutputFormat::Tsv => writeln!(
+                self.writer,
+                "# Error: the two paths representing {} have different lengths: {} and {}.",
+                path_name, length1, length2
+            )?,
+            OutputFormat::Json => {
+                self.skipped_paths
+                    .push((path_name.to_string(), length1, length2));
+            }
+            OutputFormat::JsonLines => writeln!(
+                self.writer,
+                "{{\"type\":\"skipped_path\",{}",
+                &skipped_path_record(path_name, length1, length2)[1..]
+            )?,
+        }
+        Ok(())
+    }
+
+    pub fn summary(&mut self, report: &DistanceReport) -> io::Result<()> {
+        /*
+        Writes the counts of operations and the metrics of each path, then over all paths (as a path named '*'), after all edits
+        */
+        let total: PathSummary = report.summary();
+        match self.format {
+            OutputFormat::Tsv => {
+                writeln!(self.writer, "# Summary\tPath name\tLength\tNodesA\tNodesB\tE\tS\tM\tSP\tMasked\tShared\tDistance\tDistancePerBase\tDistancePerNode\tJaccard\tDistancePerBreakpoint\tPrecision\tRecall\tF1")?;
+                for path in report.paths.iter().chain([&total]) {
+                    writeln!(
+                        self.writer,
+                        "# Summary\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.6}\t{:.6}\t{:.6}\t{:.6}\t{:.6}\t{:.6}\t{:.6}",
+                        path.path_name,
+                        path.length,
+                        path.counts.nodes_a,
+                        path.counts.nodes_b,
+                        path.counts.equivalences,
+                        path.counts.splits,
+                        path.counts.merges,
+                        path.counts.spurious,
+                        path.counts.masked,
+                        path.counts.shared,
+                        path.counts.distance(),
+                        path.distance_per_base(),
+                        path.distance_per_node(),
+                        path.jaccard(),
+                        path.distance_per_breakpoint(),
+                        path.precision(),
+                        path.recall(),
+                        path.f1()
+                    )?;
+                }
+                writeln!(
+                    self.writer,
+                    "# Distance: {} (E={}, S={}, M={}, SP={}).",
+                    report.distance(),
+                    report.counts.equivalences,
+                    report.counts.splits,
+                    report.counts.merges,
+                    report.counts.spurious
+                )?
+            }
+            OutputFormat::Json => {
+                let skipped_paths: Vec<String> = self
+                    .skipped_paths
+                    .iter()
+                    .map(|(path_name, length1, length2)| {
+                        skipped_path_record(path_name, *length1, *length2)
+                    })
+                    .collect();
+                let paths: Vec<String> = report
+                    .paths
+                    .iter()
+                    .map(|path| {
+                        format!(
+                            "{{\"path\":{},{}}}",
+                            json_string(&path.path_name),
+                            summary_fields(path)
+                        )
+                    })
+                    .collect();
+                writeln!(
+                    self.writer,
+                    "\n],\"skipped_paths\":[{}],\"path_summaries\":[{}],\"summary\":{{{}}}}}",
+                    skipped_paths.join(","),
+                    paths.join(","),
+                    summary_fields(&total)
+                )?;
+            }
+            OutputFormat::JsonLines => {
+                for path in report.paths.iter() {
+                    writeln!(
+                        self.writer,
+                        "{{\"type\":\"path_summary\",\"path\":{},{}}}",
+                        json_string(&path.path_name),
+                        summary_fields(path)
+                    )?;
+                }
+                writeln!(
+                    self.writer,
+                    "{{\"type\":\"summary\",{}}}",
+                    summary_fields(&total)
+                )?
+            }
+        }
+        self.writer.flush()
+    }
+}
+
+fn summary_fields(path: &PathSummary) -> String {
+    /*
+    Returns the fields of a path summary (or of the summary over all paths) as JSON object members
+    */
+    format!(
+        "\"length\":{},\"distance\":{},\"equivalences\":{},\"splits\":{},\"merges\":{},\"spurious\":{},\"masked\":{},\"shared\":{},\"nodes_a\":{},\"nodes_b\":{},\"distance_per_base\":{:.6},\"distance_per_node\":{:.6},\"jaccard\":{:.6},\"distance_per_breakpoint\":{:.6},\"precision\":{:.6},\"recall\":{:.6},\"f1\":{:.6}",
+        path.length,
+        path.counts.distance(),
+        path.counts.equivalences,
+        path.counts.splits,
+        path.counts.merges,
+        path.counts.spurious,
+        path.counts.masked,
+        path.counts.shared,
+        path.counts.nodes_a,
+        path.counts.nodes_b,
+        path.distance_per_base(),
+        path.distance_per_node(),
+        path.jaccard(),
+        path.distance_per_breakpoint(),
+        path.precision(),
+        path.recall(),
+        path.f1()
+    )
+}
+
+fn path_length_record(path_name: &str, length: u64) -> String {
+    format!(
+        "{{\"path\":{},\"length\":{}}}",
+        json_string(path_name),
+        length
+    )
+}
+
+fn gap_record(gap: (&str, &str, u64, u64, u64)) -> String {
+    let (graph, path_name, position, previous_end, next_start) = gap;
+    format!(
+        "{{\"graph\":{},\"path\":{},\"position\":{},\"previous_end\":{},\"next_start\":{}}}",
+        json_string(graph),
+        json_string(path_name),
+        position,
+        previous_end,
+        next_start
+    )
+}
+
+fn skipped_path_record(path_name: &str, length1: u64, length2: u64) -> String {
+    format!(
+        "{{\"path\":{},\"length_a\":{},\"length_b\":{}}}",
+        json_string(path_name),
+        length1,
+        length2
+    )
+}
+
+fn edit_record(edit: &Edit) -> String {
+    format!(
+        "{{\"path\":{},\"position\":{},\"operation\":\"{}\",\"node_a\":{},\"node_b\":{},\"breakpoint_a\":{},\"breakpoint_b\":{},\"strand_a\":\"{}\",\"strand_b\":\"{}\",\"offset_a\":{},\"offset_b\":{}}}",
+        json_string(&edit.path),
+        edit.position,
+        edit.op.as_char(),
+        json_string(&edit.node_a),
+        json_string(&edit.node_b),
+        edit.breakpoint_a,
+        edit.breakpoint_b,
+        edit.strand_a,
+        edit.strand_b,
+        edit.offset_a,
+        edit.offset_b
+    )
+}
+
+pub fn json_string(value: &str) -> String {
+    /*
+    Quotes a string for JSON, escaping quotes, backslashes and control characters
+    */
+    let mut quoted: String = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute_distance::{EditCounts, Operation};
+
+    fn edit() -> Edit {
+        Edit {
+            path: "x".to_string(),
+            position: 2,
+            op: Operation::Split,
+            node_a: "1".to_string(),
+            node_b: "4".to_string(),
+            breakpoint_a: 2,
+            breakpoint_b: 3,
+            strand_a: '+',
+            strand_b: '-',
+            offset_a: 2,
+            offset_b: 1,
+        }
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("HG002#1"), "\"HG002#1\"");
+        assert_eq!(json_string("a\"b\\c\u{1}"), "\"a\\\"b\\\\c\\u0001\"");
+    }
+
+    #[test]
+    fn test_json_lines_records() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer: ReportWriter<&mut Vec<u8>> =
+            ReportWriter::new(&mut output, OutputFormat::JsonLines);
+        writer.edit(&edit()).unwrap();
+        writer.skipped_path("y", 4, 6).unwrap();
+        writer.summary(&DistanceReport::default()).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(lines[0], "{\"type\":\"edit\",\"path\":\"x\",\"position\":2,\"operation\":\"S\",\"node_a\":\"1\",\"node_b\":\"4\",\"breakpoint_a\":2,\"breakpoint_b\":3,\"strand_a\":\"+\",\"strand_b\":\"-\",\"offset_a\":2,\"offset_b\":1}");
+        assert_eq!(
+            lines[1],
+            "{\"type\":\"skipped_path\",\"path\":\"y\",\"length_a\":4,\"length_b\":6}"
+        );
+        assert_eq!(lines[2], "{\"type\":\"summary\",\"length\":0,\"distance\":0,\"equivalences\":0,\"splits\":0,\"merges\":0,\"spurious\":0,\"masked\":0,\"shared\":0,\"nodes_a\":0,\"nodes_b\":0,\"distance_per_base\":0.000000,\"distance_per_node\":0.000000,\"jaccard\":1.000000,\"distance_per_breakpoint\":0.000000,\"precision\":1.000000,\"recall\":1.000000,\"f1\":1.000000}");
+    }
+
+    #[test]
+    fn test_json_document() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer: ReportWriter<&mut Vec<u8>> =
+            ReportWriter::new(&mut output, OutputFormat::Json);
+        writer.edit(&edit()).unwrap();
+        writer.edit(&edit()).unwrap();
+        writer.skipped_path("y", 4, 6).unwrap();
+        writer.summary(&DistanceReport::default()).unwrap();
+        let document: String = String::from_utf8(output).unwrap();
+        // Edits are separated by commas, and skipped paths come after edits
+        assert_eq!(document.matches("},\n{\"path\":\"x\"").count(), 1);
+        assert!(
+            document.contains("\"skipped_paths\":[{\"path\":\"y\",\"length_a\":4,\"length_b\":6}]")
+        );
+        assert!(document.trim_end().ends_with("\"f1\":1.000000}}"));
+    }
+
+    #[test]
+    fn test_tsv_path_summary() {
+        let mut report: DistanceReport = DistanceReport::default();
+        report.paths.push(PathSummary {
+            path_name: "x".to_string(),
+            length: 5,
+            counts: EditCounts {
+                equivalences: 2,
+                splits: 1,
+                merges: 1,
+                spurious: 0,
+                masked: 0,
+                shared: 1,
+                nodes_a: 3,
+                nodes_b: 3,
+            },
+        });
+        report.counts.add(&report.paths[0].counts.clone());
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer: ReportWriter<&mut Vec<u8>> =
+            ReportWriter::new(&mut output, OutputFormat::Tsv);
+        writer.summary(&report).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(
+            lines[1],
+            "# Summary\tx\t5\t3\t3\t2\t1\t1\t0\t0\t1\t2\t0.400000\t0.333333\t0.333333\t0.666667\t0.500000\t0.500000\t0.500000"
+        );
+        // Counts over all paths are reported as a path named '*'
+        assert!(lines[2].starts_with("# Summary\t*\t5\t3\t3\t2\t1\t1\t0\t0\t1\t2\t"));
+        assert_eq!(lines[3], "# Distance: 2 (E=2, S=1, M=1, SP=0).");
+    }
+}